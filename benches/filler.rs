@@ -1,98 +1,123 @@
 use criterion::black_box;
-use std::sync::Arc;
+use std::{fs::File, io::BufReader};
 use xwords::{
     crossword::Crossword,
     fill::{filler::Filler, Fill},
+    index::bitset::BitsetIndex,
     trie::Trie,
+    wordlist::WordListFormat,
 };
 
-use criterion::{criterion_group, criterion_main, Benchmark, Criterion};
+use criterion::{criterion_group, criterion_main, Criterion};
 
 pub fn criterion_benchmark(c: &mut Criterion) {
-    let group_id = "filler";
+    let mut group = c.benchmark_group("filler");
 
     let trie = Trie::load_default().expect("Failed to load trie");
 
-    let trie = Arc::new(trie);
-
-    let tmp_trie = trie.clone();
-
-    c.bench(
-        group_id,
-        Benchmark::new("empty_20201005_crossword", move |b| {
-            let mut filler = Filler::new(tmp_trie.as_ref());
-
-            let input = std::fs::read_to_string("./grids/20201012_empty.txt")
-                .expect("failed to read input");
-            let input = Crossword::square(input).expect("failed to parse input");
-
-            b.iter(|| {
-                assert!(filler.fill(black_box(&input)).is_ok());
-            });
-        }),
-    );
-
-    let tmp_trie = trie.clone();
-    c.bench(
-        group_id,
-        Benchmark::new("empty_20201012_crossword", move |b| {
-            let input = std::fs::read_to_string("./grids/20201012_empty.txt")
-                .expect("failed to read input");
-            let input = Crossword::square(input).expect("failed to parse input");
-            let mut filler = Filler::new(tmp_trie.as_ref());
-            b.iter(|| {
-                assert!(filler.fill(black_box(&input)).is_ok());
-            });
-        }),
-    );
-
-    let tmp_trie = trie.clone();
-
-    c.bench(
-        group_id,
-        Benchmark::new("empty_20201107_crossword", move |b| {
-            let mut filler = Filler::new(tmp_trie.as_ref());
-            let input = std::fs::read_to_string("./grids/20201107_empty.txt")
-                .expect("failed to read input");
-            let input = Crossword::square(input).expect("failed to parse input");
-
-            b.iter(|| {
-                assert!(filler.fill(black_box(&input)).is_ok());
-            });
-        }),
-    );
-
-    let tmp_trie = trie.clone();
-
-    c.bench(
-        group_id,
-        Benchmark::new("empty_20201128_crossword", move |b| {
-            let mut filler = Filler::new(tmp_trie.as_ref());
-            let input = std::fs::read_to_string("./grids/20201128_empty.txt")
-                .expect("failed to read input");
-            let input = Crossword::square(input).expect("failed to parse input");
-
-            b.iter(|| {
-                assert!(filler.fill(black_box(&input)).is_ok());
-            });
-        }),
-    );
-
-    let tmp_trie = trie.clone();
-
-    c.bench(
-        group_id,
-        Benchmark::new("empty_20201124_crossword", move |b| {
-            let mut filler = Filler::new(tmp_trie.as_ref());
-            let input = std::fs::read_to_string("./grids/20201124_empty.txt")
-                .expect("failed to read input");
-            let input = Crossword::rectangle(input, 16, 15).expect("failed to parse input");
-
-            b.iter(|| {
-                assert!(filler.fill(black_box(&input)).is_ok());
-            });
-        }),
-    );
+    group.bench_function("empty_20201005_crossword", |b| {
+        let mut filler = Filler::new(&trie);
+
+        let input =
+            std::fs::read_to_string("./grids/20201012_empty.txt").expect("failed to read input");
+        let input = Crossword::square(input).expect("failed to parse input");
+
+        b.iter(|| {
+            assert!(filler.fill(black_box(&input)).is_ok());
+        });
+    });
+
+    group.bench_function("empty_20201012_crossword", |b| {
+        let input =
+            std::fs::read_to_string("./grids/20201012_empty.txt").expect("failed to read input");
+        let input = Crossword::square(input).expect("failed to parse input");
+        let mut filler = Filler::new(&trie);
+        b.iter(|| {
+            assert!(filler.fill(black_box(&input)).is_ok());
+        });
+    });
+
+    group.bench_function("empty_20201107_crossword", |b| {
+        let mut filler = Filler::new(&trie);
+        let input =
+            std::fs::read_to_string("./grids/20201107_empty.txt").expect("failed to read input");
+        let input = Crossword::square(input).expect("failed to parse input");
+
+        b.iter(|| {
+            assert!(filler.fill(black_box(&input)).is_ok());
+        });
+    });
+
+    group.bench_function("empty_20201128_crossword", |b| {
+        let mut filler = Filler::new(&trie);
+        let input =
+            std::fs::read_to_string("./grids/20201128_empty.txt").expect("failed to read input");
+        let input = Crossword::square(input).expect("failed to parse input");
+
+        b.iter(|| {
+            assert!(filler.fill(black_box(&input)).is_ok());
+        });
+    });
+
+    group.bench_function("empty_20201124_crossword", |b| {
+        let mut filler = Filler::new(&trie);
+        let input =
+            std::fs::read_to_string("./grids/20201124_empty.txt").expect("failed to read input");
+        let input = Crossword::rectangle(input, 16, 15).expect("failed to parse input");
+
+        b.iter(|| {
+            assert!(filler.fill(black_box(&input)).is_ok());
+        });
+    });
+
+    group.bench_function("propagation_20201005_crossword", |b| {
+        let mut filler = Filler::new(&trie).with_propagation(true);
+        let input =
+            std::fs::read_to_string("./grids/20201005_empty.txt").expect("failed to read input");
+        let input = Crossword::square(input).expect("failed to parse input");
+
+        b.iter(|| {
+            assert!(filler.fill(black_box(&input)).is_ok());
+        });
+    });
+
+    group.bench_function("propagation_20201128_crossword", |b| {
+        let mut filler = Filler::new(&trie).with_propagation(true);
+        let input =
+            std::fs::read_to_string("./grids/20201128_empty.txt").expect("failed to read input");
+        let input = Crossword::square(input).expect("failed to parse input");
+
+        b.iter(|| {
+            assert!(filler.fill(black_box(&input)).is_ok());
+        });
+    });
+
+    group.bench_function("backjumping_20201005_crossword", |b| {
+        let mut filler = Filler::new(&trie).with_backjumping(true);
+        let input =
+            std::fs::read_to_string("./grids/20201005_empty.txt").expect("failed to read input");
+        let input = Crossword::square(input).expect("failed to parse input");
+
+        b.iter(|| {
+            assert!(filler.fill(black_box(&input)).is_ok());
+        });
+    });
+
+    let file = File::open("wordlist.json").expect("Failed to open word list");
+    let index = BitsetIndex::from_reader(BufReader::new(file), WordListFormat::Json)
+        .expect("Failed to load index");
+
+    group.bench_function("bitset_20201012_crossword", |b| {
+        let input =
+            std::fs::read_to_string("./grids/20201012_empty.txt").expect("failed to read input");
+        let input = Crossword::square(input).expect("failed to parse input");
+        let mut filler = Filler::new(&index);
+        b.iter(|| {
+            assert!(filler.fill(black_box(&input)).is_ok());
+        });
+    });
+
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
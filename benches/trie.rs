@@ -1,39 +1,103 @@
 use criterion::black_box;
-use std::sync::Arc;
-use xwords::trie::Trie;
+use std::{fs::File, io::BufReader};
+use xwords::{
+    index::{bitset::BitsetIndex, mapped::MappedIndex, WordIndex},
+    trie::Trie,
+    wordlist::WordListFormat,
+};
 
-use criterion::{criterion_group, criterion_main, Benchmark, Criterion};
+use criterion::{criterion_group, criterion_main, Criterion};
 
 pub fn criterion_benchmark(c: &mut Criterion) {
-    let group_id = "trie";
+    let mut group = c.benchmark_group("trie");
 
     let trie = Trie::load_default().expect("Failed to load trie");
 
-    let trie = Arc::new(trie);
-
-    let tmp_trie = trie.clone();
-
-    c.bench(
-        group_id,
-        Benchmark::new("empty_word", move |b| {
-            b.iter(|| {
-                let input = "     ".chars();
-                assert!(tmp_trie.words(black_box(input)).len() > 0);
-            });
-        }),
-    );
-
-    let tmp_trie = trie.clone();
-
-    c.bench(
-        group_id,
-        Benchmark::new("partial_word", move |b| {
-            b.iter(|| {
-                let input = " E R ".chars();
-                assert!(tmp_trie.words(black_box(input)).len() > 0);
-            });
-        }),
-    );
+    group.bench_function("empty_word", |b| {
+        b.iter(|| {
+            let input = "     ".chars();
+            assert!(!trie.words(black_box(input)).is_empty());
+        });
+    });
+
+    group.bench_function("partial_word", |b| {
+        b.iter(|| {
+            let input = " E R ".chars();
+            assert!(!trie.words(black_box(input)).is_empty());
+        });
+    });
+
+    group.bench_function("leading_blanks", |b| {
+        b.iter(|| {
+            let input = "   E  S".chars();
+            assert!(!trie.words(black_box(input)).is_empty());
+        });
+    });
+
+    group.finish();
+
+    let mut group = c.benchmark_group("bitset");
+
+    let file = File::open("wordlist.json").expect("Failed to open word list");
+    let index = BitsetIndex::from_reader(BufReader::new(file), WordListFormat::Json)
+        .expect("Failed to load index");
+
+    group.bench_function("empty_word", |b| {
+        b.iter(|| {
+            let input = "     ".chars();
+            assert!(!index.words_with_min_score(black_box(input), 0).is_empty());
+        });
+    });
+
+    group.bench_function("partial_word", |b| {
+        b.iter(|| {
+            let input = " E R ".chars();
+            assert!(!index.words_with_min_score(black_box(input), 0).is_empty());
+        });
+    });
+
+    group.bench_function("leading_blanks", |b| {
+        b.iter(|| {
+            let input = "   E  S".chars();
+            assert!(!index.words_with_min_score(black_box(input), 0).is_empty());
+        });
+    });
+
+    group.bench_function("leading_blanks_count", |b| {
+        b.iter(|| {
+            let input = "   E  S".chars();
+            assert!(index.count_matches(black_box(input), 0) > 0);
+        });
+    });
+
+    group.finish();
+
+    let mut group = c.benchmark_group("mapped");
+
+    let mut bytes = vec![];
+    MappedIndex::write(&trie, &mut bytes).expect("Failed to write index");
+
+    group.bench_function("open_verified", |b| {
+        b.iter(|| MappedIndex::from_bytes(black_box(bytes.clone())).unwrap());
+    });
+
+    let index = MappedIndex::from_bytes(bytes).expect("Failed to load index");
+
+    group.bench_function("empty_word", |b| {
+        b.iter(|| {
+            let input = "     ".chars();
+            assert!(!index.words_with_min_score(black_box(input), 0).is_empty());
+        });
+    });
+
+    group.bench_function("partial_word", |b| {
+        b.iter(|| {
+            let input = " E R ".chars();
+            assert!(!index.words_with_min_score(black_box(input), 0).is_empty());
+        });
+    });
+
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
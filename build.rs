@@ -0,0 +1,25 @@
+//! Converts `wordlist.json` into the newline-delimited list that is embedded in the
+//! crate as the default word list.
+
+use std::{
+    env,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+fn main() {
+    println!("cargo:rerun-if-changed=wordlist.json");
+
+    let input = File::open("wordlist.json").expect("Failed to open wordlist.json");
+    let words: Vec<String> =
+        serde_json::from_reader(BufReader::new(input)).expect("wordlist.json is not valid JSON");
+
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is not set");
+    let output = File::create(Path::new(&out_dir).join("default_wordlist.txt"))
+        .expect("Failed to create default word list");
+    let mut output = BufWriter::new(output);
+    for word in words.iter().filter(|word| !word.trim().is_empty()) {
+        writeln!(output, "{}", word.trim()).expect("Failed to write default word list");
+    }
+}
@@ -0,0 +1,50 @@
+//! Compares how many partial fills the filler explores, and how long it takes, with
+//! and without propagation and backjumping on the grids in `grids/`. Run it with
+//! `--release`, since the time is what decides whether a technique pays off.
+
+use std::time::Instant;
+
+use xwords::{
+    crossword::Crossword,
+    fill::{filler::Filler, Fill},
+    trie::Trie,
+};
+
+fn main() -> Result<(), String> {
+    let trie = Trie::load_default()?;
+    let grids = [
+        ("20201005", None),
+        ("20201012", None),
+        ("20201107", None),
+        ("20201124", Some((16, 15))),
+        ("20201128", None),
+    ];
+
+    println!("grid      propagation  backjumping  explored  time");
+    for (name, size) in grids.iter() {
+        let input = std::fs::read_to_string(format!("./grids/{}_empty.txt", name))
+            .map_err(|err| err.to_string())?;
+        let input = match size {
+            Some((width, height)) => Crossword::rectangle(input, *width, *height)?,
+            None => Crossword::square(input)?,
+        };
+        for (propagate, backjump) in
+            [(false, false), (true, false), (false, true), (true, true)].iter()
+        {
+            let mut filler = Filler::new(&trie)
+                .with_propagation(*propagate)
+                .with_backjumping(*backjump);
+            let start = Instant::now();
+            filler.fill(&input)?;
+            println!(
+                "{:<9} {:<12} {:<12} {:>8}  {:?}",
+                name,
+                propagate,
+                backjump,
+                filler.explored(),
+                start.elapsed()
+            );
+        }
+    }
+    Ok(())
+}
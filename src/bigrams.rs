@@ -0,0 +1,228 @@
+/*!
+A letter bigram model: how likely each letter is to follow another.
+
+The model rates how "wordlike" a string is, which helps when curating word lists,
+and `Filler::with_bigrams` uses it to try the candidates whose crossings look most
+like real words first. The counts in `bigrams.bincode` are embedded in the crate
+as `BigramModel::load_default`; `BigramModel::train` counts a word list instead.
+*/
+
+use std::{collections::HashMap, fs::File, io::BufReader, io::Write, path::Path};
+
+use crate::wordlist::normalize::Normalizer;
+
+const BUNDLED_BIGRAMS: &[u8] = include_bytes!("../bigrams.bincode");
+
+/// Counts of adjacent letter pairs, smoothed into conditional probabilities.
+#[derive(Debug, Clone)]
+pub struct BigramModel {
+    normalizer: Normalizer,
+    /// `counts[first * letters + second]` is how often `second` followed `first`.
+    counts: Vec<u64>,
+    /// The natural log of the probability of `second` following `first`, with add-one
+    /// smoothing so that unseen pairs are unlikely rather than impossible.
+    log_probabilities: Vec<f32>,
+}
+
+impl BigramModel {
+    /// Loads the model embedded in the crate, built from `bigrams.bincode`.
+    pub fn load_default() -> Result<BigramModel, String> {
+        let counts = bincode::deserialize(BUNDLED_BIGRAMS)
+            .map_err(|err| format!("Failed to load bundled bigrams: {}", err))?;
+        Ok(BigramModel::from_counts(Normalizer::default(), &counts))
+    }
+
+    /// Loads a model written by `write`, in the format of `bigrams.bincode`: a bincode
+    /// encoded `HashMap<(char, char), u64>`.
+    pub fn load(path: &Path) -> Result<BigramModel, String> {
+        let file = File::open(path)
+            .map_err(|err| format!("Failed to open {}: {}", path.display(), err))?;
+        let counts = bincode::deserialize_from(BufReader::new(file))
+            .map_err(|err| format!("Failed to load bigrams from {}: {}", path.display(), err))?;
+        Ok(BigramModel::from_counts(Normalizer::default(), &counts))
+    }
+
+    /// Builds a model from pair counts. Pairs are uppercased, and pairs with a
+    /// character outside `normalizer`'s alphabet are ignored.
+    pub fn from_counts(normalizer: Normalizer, counts: &HashMap<(char, char), u64>) -> BigramModel {
+        let mut model = BigramModel::empty(normalizer);
+        let alphabet = model.normalizer.alphabet();
+        let letters = alphabet.letters().len();
+        for ((first, second), count) in counts.iter() {
+            let first = first
+                .to_uppercase()
+                .next()
+                .and_then(|c| alphabet.index_of(c));
+            let second = second
+                .to_uppercase()
+                .next()
+                .and_then(|c| alphabet.index_of(c));
+            if let (Some(first), Some(second)) = (first, second) {
+                model.counts[first * letters + second] += count;
+            }
+        }
+        model.update_probabilities();
+        model
+    }
+
+    /// Counts the adjacent letter pairs of `words` after normalizing them.
+    pub fn train<S: AsRef<str>, T: IntoIterator<Item = S>>(
+        normalizer: Normalizer,
+        words: T,
+    ) -> BigramModel {
+        let mut model = BigramModel::empty(normalizer);
+        let letters = model.normalizer.alphabet().letters().len();
+        for word in words {
+            let indices: Vec<usize> = model
+                .normalizer
+                .normalize(word.as_ref())
+                .chars()
+                .filter_map(|c| model.normalizer.alphabet().index_of(c))
+                .collect();
+            for pair in indices.windows(2) {
+                model.counts[pair[0] * letters + pair[1]] += 1;
+            }
+        }
+        model.update_probabilities();
+        model
+    }
+
+    fn empty(normalizer: Normalizer) -> BigramModel {
+        let letters = normalizer.alphabet().letters().len();
+        BigramModel {
+            normalizer,
+            counts: vec![0; letters * letters],
+            log_probabilities: vec![0.0; letters * letters],
+        }
+    }
+
+    fn update_probabilities(&mut self) {
+        let letters = self.normalizer.alphabet().letters().len();
+        for first in 0..letters {
+            let row = first * letters..(first + 1) * letters;
+            let total = self.counts[row.clone()].iter().sum::<u64>() + letters as u64;
+            for index in row {
+                self.log_probabilities[index] =
+                    ((self.counts[index] + 1) as f64 / total as f64).ln() as f32;
+            }
+        }
+    }
+
+    /// Writes the pair counts in the format `load` reads.
+    pub fn write<W: Write>(&self, writer: W) -> Result<(), String> {
+        bincode::serialize_into(writer, &self.counts())
+            .map_err(|err| format!("Failed to write bigrams: {}", err))
+    }
+
+    /// Returns the number of times each pair was seen, leaving out unseen pairs.
+    pub fn counts(&self) -> HashMap<(char, char), u64> {
+        let letters = self.normalizer.alphabet().letters();
+        let mut result = HashMap::new();
+        for (index, count) in self.counts.iter().enumerate() {
+            if *count > 0 {
+                result.insert(
+                    (
+                        letters[index / letters.len()],
+                        letters[index % letters.len()],
+                    ),
+                    *count,
+                );
+            }
+        }
+        result
+    }
+
+    pub fn normalizer(&self) -> &Normalizer {
+        &self.normalizer
+    }
+
+    /// The log probability of `second` following `first`, or `None` if either is not
+    /// a letter of the alphabet.
+    pub fn log_probability(&self, first: char, second: char) -> Option<f32> {
+        let alphabet = self.normalizer.alphabet();
+        let first = alphabet.index_of(first)?;
+        let second = alphabet.index_of(second)?;
+        Some(self.log_probabilities[first * alphabet.letters().len() + second])
+    }
+
+    /// Rates how much `text` looks like a word: the average log probability of its
+    /// letter pairs after normalization. Scores are at most 0; English words mostly
+    /// score above -3, while strings like `XQZJ` score below -6. Returns `None` if
+    /// `text` has fewer than two letters.
+    pub fn wordlikeness(&self, text: &str) -> Option<f32> {
+        self.score_pattern(self.normalizer.normalize(text).chars())
+    }
+
+    /// Rates a partially filled entry: the average log probability of its pairs of
+    /// adjacent filled squares, where ` ` is a blank. Returns `None` if no two filled
+    /// squares are adjacent.
+    pub fn score_pattern<T: Iterator<Item = char>>(&self, pattern: T) -> Option<f32> {
+        let mut total = 0.0;
+        let mut pairs = 0;
+        let mut previous = None;
+        for c in pattern {
+            if let Some(log_probability) = previous.and_then(|first| self.log_probability(first, c))
+            {
+                total += log_probability;
+                pairs += 1;
+            }
+            previous = Some(c);
+        }
+        if pairs == 0 {
+            None
+        } else {
+            Some(total / pairs as f32)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::wordlist::normalize::Normalizer;
+
+    use super::BigramModel;
+
+    #[test]
+    fn load_default_works() {
+        let model = BigramModel::load_default().unwrap();
+
+        assert!(model.log_probability('Q', 'U') > model.log_probability('Q', 'Z'));
+        assert!(model.wordlikeness("strength").unwrap() > -3.0);
+        assert!(model.wordlikeness("xqzjv").unwrap() < -6.0);
+        assert!(model.wordlikeness("table") > model.wordlikeness("tbale"));
+        assert_eq!(model.wordlikeness("a"), None);
+        assert!(model
+            .counts()
+            .keys()
+            .all(|(first, second)| { first.is_ascii_uppercase() && second.is_ascii_uppercase() }));
+    }
+
+    #[test]
+    fn train_works() {
+        let model = BigramModel::train(Normalizer::default(), vec!["abab", "Ab c"]);
+
+        let mut expected = HashMap::new();
+        expected.insert(('A', 'B'), 3);
+        expected.insert(('B', 'A'), 1);
+        expected.insert(('B', 'C'), 1);
+        assert_eq!(model.counts(), expected);
+        assert_eq!(model.log_probability('A', 'B'), Some((4.0f32 / 29.0).ln()));
+        assert_eq!(model.log_probability('A', '1'), None);
+
+        assert_eq!(model.score_pattern("A B".chars()), None);
+        assert_eq!(
+            model.score_pattern("AB C".chars()),
+            model.log_probability('A', 'B')
+        );
+
+        let mut bytes = vec![];
+        model.write(&mut bytes).unwrap();
+        let counts = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(
+            BigramModel::from_counts(Normalizer::default(), &counts).counts(),
+            model.counts()
+        );
+    }
+}
@@ -1,12 +1,292 @@
 extern crate clap;
-use std::fs::File;
-use xwords::{fill::Fill, trie::Trie};
+use std::{fs::File, path::Path, time::Duration};
+use xwords::{
+    bigrams::BigramModel,
+    crossword::WordIterator,
+    fill::{Fill, Objective, TagLimits},
+    formats::{read_puzzle, Format},
+    index::{mapped::MappedIndex, serialized, WordIndex},
+    parse::number_word_boundaries,
+    search::{anagrams, letter_bank, search},
+    trie::Trie,
+    wordlist::lint::{LintKind, Linter},
+};
 
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use xwords::{crossword::Crossword, fill::filler::Filler};
 
+fn convert(matches: &ArgMatches) -> Result<(), String> {
+    let input = Path::new(matches.value_of("input").expect("input not included"));
+    let output = Path::new(matches.value_of("output").expect("output not included"));
+
+    let puzzle = read_puzzle(input)?;
+    let format = Format::from_extension(output).ok_or_else(|| {
+        format!(
+            "Could not determine output format of {}; use .txt, .puz or .ipuz.",
+            output.display()
+        )
+    })?;
+
+    for lost in format.lost_features(&puzzle) {
+        eprintln!("warning: {}: {}", format, lost);
+    }
+
+    let bytes = format.write(&puzzle)?;
+    std::fs::write(output, bytes)
+        .map_err(|err| format!("Failed to write {}: {}", output.display(), err))
+}
+
+fn load_trie(matches: &ArgMatches) -> Result<Trie, String> {
+    match matches.value_of("wordlist") {
+        Some(path) => Trie::load(Path::new(path)),
+        None => Trie::load_default(),
+    }
+}
+
+/// Parses `--limit-tag` values like `partial=0`.
+fn tag_limits(matches: &ArgMatches) -> Result<TagLimits, String> {
+    let mut result = TagLimits::new();
+    for value in matches.values_of("limit-tag").into_iter().flatten() {
+        let (tag, max) = value
+            .split_once('=')
+            .ok_or_else(|| format!("Expected TAG=COUNT, got {:?}.", value))?;
+        let max = max
+            .parse()
+            .map_err(|_| format!("Invalid count {:?} for tag {}.", max, tag))?;
+        result = result.with_limit(tag.parse()?, max);
+    }
+    Ok(result)
+}
+
+/// Fills `input` with `index` and prints the result.
+fn fill<I: WordIndex>(
+    matches: &ArgMatches,
+    input: &Crossword,
+    index: &I,
+    tag_limits: TagLimits,
+    bigrams: Option<&BigramModel>,
+) -> Result<(), String> {
+    let mut filler = Filler::new(index)
+        .with_tag_limits(tag_limits)
+        .with_propagation(matches.is_present("propagate"))
+        .with_backjumping(matches.is_present("backjump"));
+    if let Some(bigrams) = bigrams {
+        filler = filler.with_bigrams(bigrams);
+    }
+    if let Some(seed) = matches.value_of("seed") {
+        let seed = seed
+            .parse()
+            .map_err(|_| format!("Invalid seed: {}", seed))?;
+        filler = filler.with_seed(seed);
+    }
+    if let Some(seconds) = matches.value_of("time-limit") {
+        let seconds: f64 = seconds
+            .parse()
+            .map_err(|_| format!("Invalid time limit: {}", seconds))?;
+        filler = filler.with_time_limit(Duration::from_secs_f64(seconds));
+    }
+    let outputs = match matches.value_of("optimize") {
+        Some(objective) => {
+            let objective = match objective {
+                "total" => Objective::TotalScore,
+                "min" => Objective::MinScore,
+                _ => unreachable!("clap only accepts total or min"),
+            };
+            let best = filler
+                .with_objective(objective)
+                .fill_best(input)
+                .map_err(|_| String::from("Failed to fill crossword"))?;
+            eprintln!(
+                "{} {}{}",
+                if objective == Objective::TotalScore {
+                    "Total score"
+                } else {
+                    "Minimum score"
+                },
+                best.value,
+                if best.optimal { " (optimal)" } else { "" }
+            );
+            vec![best.crossword]
+        }
+        None => match matches.value_of("count") {
+            Some(count) => {
+                let count: usize = count
+                    .parse()
+                    .map_err(|_| format!("Invalid count: {}", count))?;
+                if let Some(min_difference) = matches.value_of("min-difference") {
+                    let min_difference = min_difference
+                        .parse()
+                        .map_err(|_| format!("Invalid minimum difference: {}", min_difference))?;
+                    filler = filler.with_min_difference(min_difference);
+                }
+                let fills = filler
+                    .fill_distinct(input, count)
+                    .map_err(|_| String::from("Failed to fill crossword"))?;
+                if fills.len() < count {
+                    eprintln!("Found only {} of {} fills", fills.len(), count);
+                }
+                fills
+            }
+            None => vec![filler
+                .fill(input)
+                .map_err(|_| String::from("Failed to fill crossword"))?],
+        },
+    };
+
+    for output in outputs.iter() {
+        println!("{}", output);
+        if matches.is_present("entries") {
+            for (number, word_boundary) in number_word_boundaries(output) {
+                let word: String = WordIterator::new(output, &word_boundary).collect();
+                println!(
+                    "{} {:?}: {}",
+                    number,
+                    word_boundary.direction,
+                    index.display(&word)
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn search_command(matches: &ArgMatches) -> Result<(), String> {
+    let pattern = matches.value_of("pattern").expect("pattern not included");
+    let limit = match matches.value_of("limit") {
+        Some(limit) => Some(
+            limit
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid limit: {}", limit))?,
+        ),
+        None => None,
+    };
+
+    let length = match matches.value_of("length") {
+        Some(length) => Some(
+            length
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid length: {}", length))?,
+        ),
+        None => None,
+    };
+
+    let trie = load_trie(matches)?;
+    let matches = if matches.is_present("anagram") {
+        anagrams(&trie, pattern, length)?
+    } else if matches.is_present("bank") {
+        letter_bank(&trie, pattern, length)?
+    } else {
+        let mut matches = search(&trie, pattern)?;
+        if let Some(length) = length {
+            matches.retain(|(word, _)| word.chars().count() == length);
+        }
+        matches
+    };
+    for (word, score) in matches.iter().take(limit.unwrap_or(matches.len())) {
+        let display = trie.display(word);
+        if display == word {
+            println!("{}\t{}", score, word);
+        } else {
+            println!("{}\t{}\t{}", score, word, display);
+        }
+    }
+    Ok(())
+}
+
+fn lint_command(matches: &ArgMatches) -> Result<(), String> {
+    let path = Path::new(matches.value_of("input").expect("input not included"));
+    let mut linter = Linter::new();
+    if let Some(length) = matches.value_of("min-length") {
+        linter = linter.with_min_length(
+            length
+                .parse()
+                .map_err(|_| format!("Invalid length: {}", length))?,
+        );
+    }
+    if let Some(length) = matches.value_of("max-length") {
+        linter = linter.with_max_length(
+            length
+                .parse()
+                .map_err(|_| format!("Invalid length: {}", length))?,
+        );
+    }
+
+    let report = linter.lint_file(path)?;
+    if matches.is_present("json") {
+        println!("{}", report.to_json());
+    } else {
+        for issue in report.issues.iter() {
+            let problem = match &issue.kind {
+                LintKind::InvalidCharacters { characters } => {
+                    format!("invalid characters {:?}", characters)
+                }
+                LintKind::Duplicate {
+                    normalized,
+                    first_entry,
+                } => format!("duplicate of entry {} ({})", first_entry, normalized),
+                LintKind::TooShort { length } => format!("too short ({} letters)", length),
+                LintKind::TooLong { length } => format!("too long ({} letters)", length),
+            };
+            println!("{}\t{:?}\t{}", issue.entry, issue.word, problem);
+        }
+        println!(
+            "{} entries, {} distinct words, {} issues",
+            report.entries,
+            report.words,
+            report.issues.len()
+        );
+        println!("Lengths:");
+        for (length, count) in report.lengths.iter() {
+            println!("  {}\t{}", length, count);
+        }
+        println!("Scores:");
+        for (score, count) in report.scores.iter() {
+            println!("  {}\t{}", score, count);
+        }
+        println!("Letters:");
+        for (letter, count) in report.letters.iter() {
+            println!("  {}\t{}", letter, count);
+        }
+    }
+
+    if report.is_clean() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Found {} issues in {}.",
+            report.issues.len(),
+            path.display()
+        ))
+    }
+}
+
+fn build_command(matches: &ArgMatches) -> Result<(), String> {
+    let input = Path::new(matches.value_of("input").expect("input not included"));
+    let output = Path::new(matches.value_of("output").expect("output not included"));
+
+    let trie = Trie::load(input)?;
+    match output.extension().and_then(|extension| extension.to_str()) {
+        Some("bincode") => serialized::save(&trie, output)?,
+        Some("xwi") => {
+            let file = File::create(output)
+                .map_err(|err| format!("Failed to create {}: {}", output.display(), err))?;
+            MappedIndex::write(&trie, std::io::BufWriter::new(file))
+                .map_err(|err| format!("Failed to write {}: {}", output.display(), err))?;
+        }
+        _ => {
+            return Err(format!(
+                "Could not determine output format of {}; use .bincode or .xwi.",
+                output.display()
+            ))
+        }
+    }
+    println!("Wrote {} words to {}.", trie.word_count(), output.display());
+    Ok(())
+}
+
 fn main() -> Result<(), String> {
     let matches = App::new("xwords")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(
             Arg::with_name("input")
                 .short("i")
@@ -29,14 +309,248 @@ fn main() -> Result<(), String> {
                 .value_name("HEIGHT")
                 .help("Input crossword height. Required if input is not a square"),
         )
+        .arg(
+            Arg::with_name("wordlist")
+                .long("wordlist")
+                .value_name("FILE")
+                .help(
+                    "Word list to fill with (.txt, .dict, .json, .bincode or an .xwi index). \
+                     Defaults to $XWORDS_WORDLIST, then to the bundled list",
+                ),
+        )
+        .arg(
+            Arg::with_name("entries")
+                .long("entries")
+                .takes_value(false)
+                .help("Also list the filled entries, as written in the word list"),
+        )
+        .arg(
+            Arg::with_name("limit-tag")
+                .long("limit-tag")
+                .value_name("TAG=COUNT")
+                .multiple(true)
+                .number_of_values(1)
+                .help(
+                    "Use at most COUNT entries tagged TAG (proper, abbr, partial, foreign, \
+                     crosswordese or vulgar). May be repeated",
+                ),
+        )
+        .arg(
+            Arg::with_name("bigrams")
+                .long("bigrams")
+                .value_name("FILE")
+                .min_values(0)
+                .max_values(1)
+                .help(
+                    "Order the search with a letter bigram model: the bundled one, or \
+                     counts saved with BigramModel::write",
+                ),
+        )
+        .arg(
+            Arg::with_name("propagate")
+                .long("propagate")
+                .takes_value(false)
+                .help(
+                    "Narrow the letters each square may hold after every word placed, \
+                     discarding dead ends sooner. Much faster on hard grids, but up to \
+                     twice as slow on easy ones",
+                ),
+        )
+        .arg(
+            Arg::with_name("backjump")
+                .long("backjump")
+                .takes_value(false)
+                .help(
+                    "After a dead end, jump back to the placement responsible and never \
+                     retry the letters that caused it",
+                ),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("SEED")
+                .takes_value(true)
+                .help(
+                    "Try words in a random order favoring higher scores. The same SEED \
+                     gives the same fill",
+                ),
+        )
+        .arg(
+            Arg::with_name("optimize")
+                .long("optimize")
+                .value_name("OBJECTIVE")
+                .possible_values(&["total", "min"])
+                .help(
+                    "Search for the fill with the highest total entry score, or the \
+                     highest minimum entry score, instead of the first fill found",
+                ),
+        )
+        .arg(
+            Arg::with_name("count")
+                .long("count")
+                .value_name("COUNT")
+                .conflicts_with("optimize")
+                .help("Print up to COUNT distinct fills"),
+        )
+        .arg(
+            Arg::with_name("min-difference")
+                .long("min-difference")
+                .value_name("ENTRIES")
+                .requires("count")
+                .help("With --count, make every two fills differ in at least ENTRIES entries"),
+        )
+        .arg(
+            Arg::with_name("time-limit")
+                .long("time-limit")
+                .value_name("SECONDS")
+                .help(
+                    "Stop searching after SECONDS. With --optimize, print the best fill \
+                     found so far",
+                ),
+        )
         .arg(
             Arg::with_name("profile")
                 .short("p")
                 .long("profile")
                 .takes_value(false),
         )
+        .subcommand(
+            SubCommand::with_name("convert")
+                .about("Converts a puzzle between the text, .puz and .ipuz formats")
+                .arg(
+                    Arg::with_name("input")
+                        .value_name("INPUT")
+                        .help("Puzzle to convert; the format is detected from its contents")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .value_name("OUTPUT")
+                        .help("Destination; the format is chosen by extension")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("search")
+                .about(
+                    "Lists words matching a pattern, or spelled with a set of letters, \
+                     highest scoring first",
+                )
+                .arg(
+                    Arg::with_name("pattern")
+                        .value_name("PATTERN")
+                        .help(
+                            "Letters, ? for any letter, [ABC] or [^ABC] for a letter from \
+                             (or not from) a set, and * for any run of letters",
+                        )
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("wordlist")
+                        .long("wordlist")
+                        .value_name("FILE")
+                        .help(
+                            "Word list to search (.txt, .dict, .json or .bincode). Defaults \
+                             to the default word list",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("anagram")
+                        .long("anagram")
+                        .takes_value(false)
+                        .help(
+                            "Treat PATTERN as letters to anagram, using each once; \
+                             ? is a blank. With --length, words may use fewer letters",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("bank")
+                        .long("bank")
+                        .conflicts_with("anagram")
+                        .help("Treat PATTERN as a letter bank whose letters may be reused"),
+                )
+                .arg(
+                    Arg::with_name("length")
+                        .short("l")
+                        .long("length")
+                        .value_name("LENGTH")
+                        .help("Only list words of this length"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .short("n")
+                        .long("limit")
+                        .value_name("COUNT")
+                        .help("Print at most COUNT matches"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("wordlist")
+                .about("Tools for maintaining word lists")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("build")
+                        .about(
+                            "Builds a word list into a serialized trie (.bincode) or a \
+                             memory-mapped index (.xwi) for faster loading",
+                        )
+                        .arg(
+                            Arg::with_name("input")
+                                .value_name("INPUT")
+                                .help("Word list to build (.txt, .dict or .json)")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::with_name("output")
+                                .value_name("OUTPUT")
+                                .help("Destination; the format is chosen by extension")
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("lint")
+                        .about(
+                            "Reports invalid characters, duplicates and out of range lengths, \
+                             with length, score and letter statistics",
+                        )
+                        .arg(
+                            Arg::with_name("input")
+                                .value_name("FILE")
+                                .help("Word list to check (.txt, .dict or .json)")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::with_name("json")
+                                .long("json")
+                                .help("Print the report as JSON"),
+                        )
+                        .arg(
+                            Arg::with_name("min-length")
+                                .long("min-length")
+                                .value_name("LENGTH")
+                                .help("Flag words shorter than this. Defaults to 3"),
+                        )
+                        .arg(
+                            Arg::with_name("max-length")
+                                .long("max-length")
+                                .value_name("LENGTH")
+                                .help("Flag words longer than this. Defaults to 21"),
+                        ),
+                ),
+        )
         .get_matches();
 
+    match matches.subcommand() {
+        ("convert", Some(matches)) => return convert(matches),
+        ("search", Some(matches)) => return search_command(matches),
+        ("wordlist", Some(matches)) => match matches.subcommand() {
+            ("build", Some(matches)) => return build_command(matches),
+            ("lint", Some(matches)) => return lint_command(matches),
+            _ => unreachable!("clap requires a wordlist subcommand"),
+        },
+        _ => {}
+    }
+
     let input = matches.value_of("input").expect("input not included");
     let input = std::fs::read_to_string(input).expect("failed to read input");
 
@@ -62,14 +576,32 @@ fn main() -> Result<(), String> {
         });
     }
 
-    let trie = Trie::load_default().expect("Failed to load trie");
-    let output = Filler::new(&trie).fill(&input);
-
-    match output {
-        Ok(output) => {
-            println!("{}", output);
-        }
-        Err(_) => return Err(String::from("Failed to fill crossword")),
+    let tag_limits = tag_limits(&matches)?;
+    let bigrams = if matches.is_present("bigrams") {
+        Some(match matches.value_of("bigrams") {
+            Some(path) => BigramModel::load(Path::new(path))?,
+            None => BigramModel::load_default()?,
+        })
+    } else {
+        None
+    };
+    // Memory mapped indexes open without deserializing anything, which matters for
+    // small grids. Checking the checksum still reads the file once, but a damaged
+    // index is reported instead of giving wrong matches.
+    match matches.value_of("wordlist").map(Path::new) {
+        Some(path) if path.extension().is_some_and(|extension| extension == "xwi") => fill(
+            &matches,
+            &input,
+            &MappedIndex::open(path)?,
+            tag_limits,
+            bigrams.as_ref(),
+        ),
+        _ => fill(
+            &matches,
+            &input,
+            &load_trie(&matches)?,
+            tag_limits,
+            bigrams.as_ref(),
+        ),
     }
-    Ok(())
 }
@@ -0,0 +1,380 @@
+/*!
+Utilities to parse clue lists and match them to the numbered entries of a `Crossword`.
+*/
+
+use std::fmt;
+
+use crate::{
+    crossword::{Crossword, Direction},
+    parse::{number_word_boundaries, WordBoundary},
+};
+
+/// The enumeration attached to a clue, e.g. the `(3)` in `Feline pet (3)` or the
+/// `(5,3)` in `Kind of party (5,3)`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Enumeration {
+    /// The enumeration as written, without the surrounding parentheses.
+    pub text: String,
+    /// The length of each word in the answer.
+    pub lengths: Vec<usize>,
+}
+
+impl Enumeration {
+    /// Parses the contents of an enumeration, e.g. `3`, `5,3` or `2-4`. Returns `None` if
+    /// the input is not made up of numbers separated by `,`, `-`, `/`, `'` or spaces.
+    pub fn parse(text: &str) -> Option<Enumeration> {
+        let mut lengths = vec![];
+        for part in text.split([',', '-', '/', '\'', ' ']) {
+            if part.is_empty() {
+                continue;
+            }
+            lengths.push(part.parse().ok()?);
+        }
+        if lengths.is_empty() {
+            return None;
+        }
+        Some(Enumeration {
+            text: String::from(text),
+            lengths,
+        })
+    }
+
+    /// The number of squares the answer occupies in the grid.
+    pub fn total_length(&self) -> usize {
+        self.lengths.iter().sum()
+    }
+}
+
+impl fmt::Display for Enumeration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({})", self.text)
+    }
+}
+
+/// A single clue, as read from a clue list.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Clue {
+    pub number: usize,
+    pub direction: Direction,
+    pub text: String,
+    pub enumeration: Option<Enumeration>,
+}
+
+impl fmt::Display for Clue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}. {}", self.number, self.text)?;
+        if let Some(enumeration) = &self.enumeration {
+            write!(f, " {}", enumeration)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a clue list such as
+///
+/// ```text
+/// Across
+/// 1. Feline pet (3)
+/// 4. Canine pet (3)
+/// Down
+/// 1. Taxi (3)
+/// ```
+///
+/// Section headings (`Across`, `Down`) are case insensitive and may end in a colon.
+/// Clue numbers may be followed by `.`, `)` or `:`, and enumerations are optional. A
+/// line that does not start with a number continues the text of the previous clue.
+///
+/// Returns an Err naming the offending line if a clue appears before any heading or a
+/// clue number is repeated within a section.
+pub fn parse_clues(text: &str) -> Result<Vec<Clue>, String> {
+    let mut result: Vec<Clue> = vec![];
+    let mut direction = None;
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(heading) = parse_heading(line) {
+            direction = Some(heading);
+            continue;
+        }
+
+        let current_direction = match &direction {
+            Some(direction) => direction.clone(),
+            None => {
+                return Err(format!(
+                    "Line {}: expected an Across or Down heading before clues.",
+                    line_number
+                ))
+            }
+        };
+
+        match split_clue_number(line) {
+            Some((number, rest)) => {
+                if result
+                    .iter()
+                    .any(|clue| clue.number == number && clue.direction == current_direction)
+                {
+                    return Err(format!(
+                        "Line {}: duplicate clue {} {:?}.",
+                        line_number, number, current_direction
+                    ));
+                }
+                let (text, enumeration) = split_enumeration(rest);
+                result.push(Clue {
+                    number,
+                    direction: current_direction,
+                    text,
+                    enumeration,
+                });
+            }
+            None => match result.last_mut() {
+                Some(previous) if previous.direction == current_direction => {
+                    let (text, enumeration) = split_enumeration(line);
+                    if !previous.text.is_empty() && !text.is_empty() {
+                        previous.text.push(' ');
+                    }
+                    previous.text.push_str(&text);
+                    if enumeration.is_some() {
+                        previous.enumeration = enumeration;
+                    }
+                }
+                _ => {
+                    return Err(format!(
+                        "Line {}: expected a clue number at the start of the line.",
+                        line_number
+                    ))
+                }
+            },
+        }
+    }
+
+    Ok(result)
+}
+
+fn parse_heading(line: &str) -> Option<Direction> {
+    let heading = line.trim_end_matches(':').trim().to_lowercase();
+    match heading.as_str() {
+        "across" => Some(Direction::Across),
+        "down" => Some(Direction::Down),
+        _ => None,
+    }
+}
+
+fn split_clue_number(line: &str) -> Option<(usize, &str)> {
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    let number = line[..digits].parse().ok()?;
+    let rest = &line[digits..];
+    let rest = rest.strip_prefix(['.', ')', ':']).unwrap_or(rest);
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some((number, rest.trim()))
+}
+
+pub(crate) fn split_enumeration(text: &str) -> (String, Option<Enumeration>) {
+    let text = text.trim();
+    if text.ends_with(')') {
+        if let Some(open) = text.rfind('(') {
+            if let Some(enumeration) = Enumeration::parse(&text[open + 1..text.len() - 1]) {
+                return (String::from(text[..open].trim_end()), Some(enumeration));
+            }
+        }
+    }
+    (String::from(text), None)
+}
+
+/// A way in which a clue list disagrees with the grid it is meant for.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ClueMismatch {
+    /// A clue refers to a number and direction that is not an entry in the grid.
+    NoSuchEntry { number: usize, direction: Direction },
+    /// An entry in the grid has no clue.
+    MissingClue { number: usize, direction: Direction },
+    /// A clue's enumeration does not add up to the length of its entry.
+    WrongLength {
+        number: usize,
+        direction: Direction,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for ClueMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClueMismatch::NoSuchEntry { number, direction } => {
+                write!(
+                    f,
+                    "{} {:?} is clued but is not in the grid",
+                    number, direction
+                )
+            }
+            ClueMismatch::MissingClue { number, direction } => {
+                write!(f, "{} {:?} has no clue", number, direction)
+            }
+            ClueMismatch::WrongLength {
+                number,
+                direction,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} {:?} is {} squares long but its enumeration totals {}",
+                number, direction, expected, actual
+            ),
+        }
+    }
+}
+
+/// The result of matching a clue list to a `Crossword`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ClueMatches<'s> {
+    /// Every clue that refers to an entry in the grid, in clue list order.
+    pub matched: Vec<(usize, WordBoundary, &'s Clue)>,
+    pub mismatches: Vec<ClueMismatch>,
+}
+
+/// Matches clues to the numbered entries of a `Crossword`, reporting clues without
+/// entries, entries without clues and enumerations that do not fit their entry.
+pub fn match_clues<'s>(crossword: &Crossword, clues: &'s [Clue]) -> ClueMatches<'s> {
+    let entries = number_word_boundaries(crossword);
+
+    let mut matched = vec![];
+    let mut mismatches = vec![];
+
+    for clue in clues {
+        let entry = entries.iter().find(|(number, word_boundary)| {
+            *number == clue.number && word_boundary.direction == clue.direction
+        });
+
+        match entry {
+            Some((number, word_boundary)) => {
+                if let Some(enumeration) = &clue.enumeration {
+                    if enumeration.total_length() != word_boundary.length {
+                        mismatches.push(ClueMismatch::WrongLength {
+                            number: *number,
+                            direction: clue.direction.clone(),
+                            expected: word_boundary.length,
+                            actual: enumeration.total_length(),
+                        });
+                    }
+                }
+                matched.push((*number, word_boundary.clone(), clue));
+            }
+            None => mismatches.push(ClueMismatch::NoSuchEntry {
+                number: clue.number,
+                direction: clue.direction.clone(),
+            }),
+        }
+    }
+
+    for (number, word_boundary) in entries.iter() {
+        if !clues
+            .iter()
+            .any(|clue| clue.number == *number && clue.direction == word_boundary.direction)
+        {
+            mismatches.push(ClueMismatch::MissingClue {
+                number: *number,
+                direction: word_boundary.direction.clone(),
+            });
+        }
+    }
+
+    ClueMatches {
+        matched,
+        mismatches,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{crossword::Direction, Crossword};
+
+    use super::{match_clues, parse_clues, ClueMismatch, Enumeration};
+
+    #[test]
+    fn parse_clues_works() {
+        let clues = parse_clues(
+            "
+Across
+1. Feline pet (3)
+4) Kind of party
+   (5,3)
+
+DOWN:
+1 Taxi (3)
+2. Question starter
+",
+        )
+        .unwrap();
+
+        assert_eq!(clues.len(), 4);
+        assert_eq!(clues[0].number, 1);
+        assert_eq!(clues[0].direction, Direction::Across);
+        assert_eq!(clues[0].text, "Feline pet");
+        assert_eq!(clues[0].enumeration, Enumeration::parse("3"));
+
+        assert_eq!(clues[1].number, 4);
+        assert_eq!(clues[1].text, "Kind of party");
+        assert_eq!(clues[1].enumeration.as_ref().unwrap().lengths, vec![5, 3]);
+
+        assert_eq!(clues[2].direction, Direction::Down);
+        assert_eq!(clues[2].text, "Taxi");
+
+        assert_eq!(clues[3].text, "Question starter");
+        assert_eq!(clues[3].enumeration, None);
+    }
+
+    #[test]
+    fn parse_clues_rejects_clues_without_heading() {
+        assert!(parse_clues("1. Feline pet (3)").is_err());
+        assert!(parse_clues("Across\n1. Cat (3)\n1. Dog (3)").is_err());
+    }
+
+    #[test]
+    fn match_clues_works() {
+        let crossword = Crossword::square(String::from("   \n * \n   ")).unwrap();
+
+        let clues = parse_clues(
+            "
+Across
+1. Feline pet (3)
+3. Canine pet (4)
+4. Not in this grid (3)
+Down
+1. Taxi (3)
+",
+        )
+        .unwrap();
+
+        let result = match_clues(&crossword, &clues);
+
+        assert_eq!(result.matched.len(), 3);
+        assert_eq!(
+            result.mismatches,
+            vec![
+                ClueMismatch::WrongLength {
+                    number: 3,
+                    direction: Direction::Across,
+                    expected: 3,
+                    actual: 4,
+                },
+                ClueMismatch::NoSuchEntry {
+                    number: 4,
+                    direction: Direction::Across
+                },
+                ClueMismatch::MissingClue {
+                    number: 2,
+                    direction: Direction::Down
+                },
+            ]
+        );
+    }
+}
@@ -2,7 +2,7 @@
 Core types to represent a crossword puzzle.
 */
 
-use crate::parse::WordBoundary;
+use crate::{parse::WordBoundary, wordlist::normalize::Normalizer};
 use std::{fmt, hash::Hash};
 
 /// The underlying representation of a crossword puzzle. All of
@@ -12,6 +12,10 @@ use std::{fmt, hash::Hash};
 /// In the contents, `*` represents a shaded square, and a ` ` represents
 /// a blank square.
 ///
+/// Letters are normalized when a grid is parsed (see
+/// [`xwords::wordlist::normalize::Normalizer::normalize_square`]), so `a` and `á` are
+/// both stored as `A`.
+///
 /// To parse a square grid, see [`xwords::crossword::Crossword::square`]. To parse a
 /// rectangular grid, see [`xwords::crossword::Crossword::rectangle`]
 
@@ -26,7 +30,7 @@ impl Crossword {
     /// Parses a crossword. Assumes that grid width and height are equal and returns
     /// an Err if not. Newlines are removed.
     pub fn square(contents: String) -> Result<Crossword, String> {
-        let without_newlines: String = contents.chars().filter(|c| *c != '\n').collect();
+        let without_newlines = normalize_grid(&contents)?;
 
         let width = (without_newlines.len() as f64).sqrt() as usize;
         if width * width != without_newlines.len() {
@@ -43,7 +47,7 @@ impl Crossword {
     /// of the input does not match the input dimensions, an Err is returned. Newlines are
     /// removed.
     pub fn rectangle(contents: String, width: usize, height: usize) -> Result<Crossword, String> {
-        let without_newlines: String = contents.chars().filter(|c| *c != '\n').collect();
+        let without_newlines = normalize_grid(&contents)?;
         if without_newlines.len() != width * height {
             return Err(String::from("Contents do not match specified dimensions"));
         }
@@ -55,6 +59,15 @@ impl Crossword {
     }
 }
 
+fn normalize_grid(contents: &str) -> Result<String, String> {
+    let normalizer = Normalizer::default();
+    contents
+        .chars()
+        .filter(|c| *c != '\n')
+        .map(|c| normalizer.normalize_square(c))
+        .collect()
+}
+
 /// An `Iterator<char>` that correctly traversing a Crossword, accounting for direction.
 ///
 /// The length of the word is stored in the `word_boundary`.
@@ -177,7 +190,7 @@ ghi
         assert!(result.is_ok());
 
         let c = result.unwrap();
-        assert_eq!(String::from("abcdefghi"), c.contents);
+        assert_eq!(String::from("ABCDEFGHI"), c.contents);
         assert_eq!(3, c.width);
         assert_eq!(3, c.height);
         println!("{}", c);
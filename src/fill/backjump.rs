@@ -0,0 +1,318 @@
+/*!
+Conflict-directed backjumping and nogood learning.
+
+The filler places one word per level of its search. When no word fits a slot,
+backtracking chronologically tries the next word of the slot placed just before,
+even when that slot had nothing to do with the failure. Instead, each level keeps a
+conflict set: the earlier levels whose letters its failures depended on. Once every
+word of a level has failed, the search jumps back to the latest level in its
+conflict set, skips the remaining words of every level in between, and hands the
+rest of the conflict set on to the level it jumped to.
+
+An exhausted level also proves that the letters its conflict set placed cannot be
+completed, whatever order they are placed in. These nogoods are kept for the rest
+of the search, and partial fills that contain one are discarded before they are
+explored.
+*/
+
+use crate::{Crossword, FxHashMap};
+
+use super::propagation::Propagator;
+
+/// Nogoods are no longer learned once this many are kept.
+const MAX_NOGOODS: usize = 100_000;
+
+/// What the search should do with a partial fill it takes off its stack.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Resume {
+    Explore,
+    /// A jump back passed over the level that placed its last word.
+    Skip,
+    /// Some level failed regardless of earlier levels, so the grid has no fill.
+    Exhausted,
+}
+
+struct Level {
+    /// The slot this level fills.
+    slot: usize,
+    /// The partial fill before this level placed a word.
+    parent: Crossword,
+    /// Whether each earlier level is in the conflict set.
+    conflicts: Vec<bool>,
+    /// Whether `conflicts` already includes the owners of the neighborhood of `slot`.
+    neighborhood: bool,
+}
+
+/// Tracks the levels of one search, their conflict sets, and the nogoods learned.
+pub struct Backjumper<'s> {
+    propagator: &'s Propagator<'s>,
+    /// Squares that were filled before the search started, which no level owns.
+    prefilled: Vec<bool>,
+    /// For each slot, once computed, the squares whose letters decide whether a word
+    /// fits it: its own, those of the slots crossing it, and those of the slots
+    /// crossing them.
+    neighborhoods: Vec<Option<Vec<usize>>>,
+    levels: Vec<Level>,
+    /// Squares and letters that no fill contains all of.
+    nogoods: Vec<Vec<(usize, u8)>>,
+    /// The nogoods containing each square and letter.
+    nogoods_by_letter: FxHashMap<(usize, u8), Vec<usize>>,
+}
+
+impl<'s> Backjumper<'s> {
+    pub fn new(propagator: &'s Propagator<'s>, initial_crossword: &Crossword) -> Backjumper<'s> {
+        Backjumper {
+            propagator,
+            prefilled: initial_crossword
+                .contents
+                .chars()
+                .map(|c| c != ' ')
+                .collect(),
+            neighborhoods: vec![None; propagator.slot_count()],
+            levels: vec![],
+            nogoods: vec![],
+            nogoods_by_letter: FxHashMap::default(),
+        }
+    }
+
+    /// The number of nogoods learned so far.
+    pub fn nogoods(&self) -> usize {
+        self.nogoods.len()
+    }
+
+    /// Called with the depth, the number of words placed, of each partial fill taken
+    /// off the stack. Every level at least as deep has no words left, so they are
+    /// closed, jumping back as their conflict sets allow. Partial fills from levels
+    /// that a jump passed over are skipped.
+    pub fn resume(&mut self, depth: usize) -> Resume {
+        if depth > self.levels.len() {
+            return Resume::Skip;
+        }
+        while self.levels.len() > depth {
+            let level = self.levels.pop().unwrap();
+            let culprit = match level.conflicts.iter().rposition(|conflict| *conflict) {
+                Some(culprit) => culprit,
+                None => return Resume::Exhausted,
+            };
+            self.learn(&level);
+
+            let culprit_conflicts = &mut self.levels[culprit].conflicts;
+            for (earlier, conflict) in level.conflicts[..culprit].iter().enumerate() {
+                culprit_conflicts[earlier] |= conflict;
+            }
+            self.levels.truncate(culprit + 1);
+            if depth > culprit + 1 {
+                return Resume::Skip;
+            }
+        }
+        Resume::Explore
+    }
+
+    /// Opens a level that fills `slot` of `parent`. The words tried are those that
+    /// match the letters already in the slot, so the levels that placed them are in
+    /// its conflict set from the start.
+    pub fn descend(&mut self, slot: usize, parent: &Crossword) {
+        self.levels.push(Level {
+            slot,
+            parent: parent.clone(),
+            conflicts: vec![false; self.levels.len()],
+            neighborhood: false,
+        });
+        for square in self.propagator.squares(slot) {
+            self.conflict_with_square(*square);
+        }
+    }
+
+    /// Records that a word failed for a reason local to the slot being filled: a
+    /// crossing slot has no matching word, or a square of a crossing slot has no
+    /// letter that its other slot allows.
+    pub fn conflict_with_neighborhood(&mut self) {
+        let level = match self.levels.last_mut() {
+            Some(level) if !level.neighborhood => level,
+            _ => return,
+        };
+        level.neighborhood = true;
+        let slot = level.slot;
+        let squares = match self.neighborhoods[slot].take() {
+            Some(squares) => squares,
+            None => self.neighborhood(slot),
+        };
+        for square in squares.iter() {
+            self.conflict_with_square(*square);
+        }
+        self.neighborhoods[slot] = Some(squares);
+    }
+
+    /// Records that a word failed for a reason that may depend on any earlier level,
+    /// such as tag limits or propagation.
+    pub fn conflict_with_all(&mut self) {
+        if let Some(level) = self.levels.last_mut() {
+            level
+                .conflicts
+                .iter_mut()
+                .for_each(|conflict| *conflict = true);
+        }
+    }
+
+    /// Returns whether `candidate`, the parent of the current level with one word
+    /// placed, contains a nogood, recording the levels that placed it if so.
+    pub fn violates_nogood(&mut self, candidate: &Crossword) -> bool {
+        let level = match self.levels.last() {
+            Some(level) => level,
+            None => return false,
+        };
+        let bytes = candidate.contents.as_bytes();
+        let parent = level.parent.contents.as_bytes();
+        let violated = self
+            .propagator
+            .squares(level.slot)
+            .iter()
+            .filter(|square| parent[**square] == b' ')
+            .filter_map(|square| self.nogoods_by_letter.get(&(*square, bytes[*square])))
+            .flatten()
+            .find(|nogood| {
+                self.nogoods[**nogood]
+                    .iter()
+                    .all(|(square, letter)| bytes[*square] == *letter)
+            })
+            .copied();
+
+        match violated {
+            Some(nogood) => {
+                for index in 0..self.nogoods[nogood].len() {
+                    let square = self.nogoods[nogood][index].0;
+                    self.conflict_with_square(square);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds the level that first filled `square`, if earlier than the current one,
+    /// to the current level's conflict set.
+    fn conflict_with_square(&mut self, square: usize) {
+        let current = match self.levels.len().checked_sub(1) {
+            Some(current) => current,
+            None => return,
+        };
+        if let Some(owner) = self.owner(square).filter(|owner| *owner < current) {
+            self.levels[current].conflicts[owner] = true;
+        }
+    }
+
+    /// The level that first filled `square`.
+    fn owner(&self, square: usize) -> Option<usize> {
+        if self.prefilled[square] {
+            return None;
+        }
+        self.levels
+            .iter()
+            .position(|level| self.propagator.squares(level.slot).contains(&square))
+    }
+
+    fn neighborhood(&self, slot: usize) -> Vec<usize> {
+        let mut slots = vec![slot];
+        for _ in 0..2 {
+            let crossing: Vec<usize> = slots
+                .iter()
+                .flat_map(|slot| self.propagator.squares(*slot))
+                .flat_map(|square| self.propagator.slots_through(*square))
+                .copied()
+                .collect();
+            slots.extend(crossing);
+            slots.sort_unstable();
+            slots.dedup();
+        }
+        let mut squares: Vec<usize> = slots
+            .iter()
+            .flat_map(|slot| self.propagator.squares(*slot))
+            .copied()
+            .collect();
+        squares.sort_unstable();
+        squares.dedup();
+        squares
+    }
+
+    /// Remembers the letters that the conflict set of the exhausted `level` placed.
+    fn learn(&mut self, level: &Level) {
+        // A nogood of every letter placed so far would only match this same partial
+        // fill again.
+        if self.nogoods.len() >= MAX_NOGOODS || level.conflicts.iter().all(|conflict| *conflict) {
+            return;
+        }
+        let parent = level.parent.contents.as_bytes();
+        let nogood: Vec<(usize, u8)> = (0..parent.len())
+            .filter(|square| {
+                self.owner(*square)
+                    .is_some_and(|owner| level.conflicts.get(owner) == Some(&true))
+            })
+            .map(|square| (square, parent[square]))
+            .collect();
+
+        let id = self.nogoods.len();
+        for letter in nogood.iter() {
+            self.nogoods_by_letter.entry(*letter).or_default().push(id);
+        }
+        self.nogoods.push(nogood);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        crossword::{Crossword, Direction},
+        fill::propagation::Propagator,
+        parse::parse_word_boundaries,
+    };
+
+    use super::{Backjumper, Resume};
+
+    #[test]
+    fn backjumping_works() {
+        let grid = Crossword::rectangle(String::from("      * *      "), 5, 3).unwrap();
+        let word_boundaries = parse_word_boundaries(&grid);
+        let slot = |direction, start_col| {
+            word_boundaries
+                .iter()
+                .position(|word_boundary| {
+                    word_boundary.direction == direction
+                        && word_boundary.start_row == 0
+                        && word_boundary.start_col == start_col
+                })
+                .unwrap()
+        };
+        let propagator = Propagator::new(&grid, &word_boundaries);
+        let mut backjumper = Backjumper::new(&propagator, &grid);
+
+        assert_eq!(backjumper.resume(0), Resume::Explore);
+        backjumper.descend(slot(Direction::Across, 0), &grid);
+        let top = Crossword::rectangle(String::from("ABCDE * *      "), 5, 3).unwrap();
+
+        assert_eq!(backjumper.resume(1), Resume::Explore);
+        backjumper.descend(slot(Direction::Down, 0), &top);
+        let left = Crossword::rectangle(String::from("ABCDEK* *F     "), 5, 3).unwrap();
+
+        // If no word for the right column starts with E, only the top row is to blame,
+        // so the search jumps past the other words for the left column.
+        assert_eq!(backjumper.resume(2), Resume::Explore);
+        backjumper.descend(slot(Direction::Down, 4), &left);
+        assert_eq!(backjumper.resume(2), Resume::Skip);
+        assert_eq!(backjumper.resume(2), Resume::Skip);
+        assert_eq!(backjumper.nogoods(), 1);
+        assert_eq!(backjumper.resume(1), Resume::Explore);
+
+        // Whatever order they are placed in, the letters of the top row fail again.
+        backjumper.descend(slot(Direction::Down, 0), &grid);
+        let left = Crossword::rectangle(String::from("A    K* *F     "), 5, 3).unwrap();
+        assert!(!backjumper.violates_nogood(&left));
+        assert_eq!(backjumper.resume(2), Resume::Explore);
+        backjumper.descend(slot(Direction::Across, 0), &left);
+        assert!(backjumper.violates_nogood(&top));
+
+        // Nothing but the left column led to the top row failing, and nothing led to
+        // that.
+        assert_eq!(backjumper.resume(1), Resume::Explore);
+        assert_eq!(backjumper.resume(0), Resume::Exhausted);
+    }
+}
@@ -1,65 +1,242 @@
 /*!
 Data stores that store the result of partial word lookups
 to prevent repeated work.
+
+The caches remember the `WordIndex::generation` they were filled from and start
+over when they are used with an index whose words have changed. A `Filler` holds on
+to its index, so it is handed a changed one with `Filler::set_index`.
 */
 
-use std::hash::{Hash, Hasher};
+use std::{
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
 
 use rustc_hash::{FxHashMap, FxHasher};
 
-use crate::trie::Trie;
+use crate::{index::WordIndex, search::LetterOptions, trie::Score};
+
+/// Hashes a partially filled word, or the letters allowed in each of its squares,
+/// and the minimum score it was looked up with.
+fn cache_key<H: Hash, T: Iterator<Item = H>>(iter: T, min_score: Score) -> u64 {
+    let mut hasher = FxHasher::default();
+    for item in iter {
+        item.hash(&mut hasher);
+    }
+    min_score.hash(&mut hasher);
+    hasher.finish()
+}
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct CachedWords {
     words_cache: FxHashMap<u64, Vec<String>>,
+    generation: u64,
 }
 
 impl CachedWords {
-    pub fn default() -> CachedWords {
-        CachedWords {
-            words_cache: FxHashMap::default(),
+    pub fn words<T: Iterator<Item = char> + Clone, I: WordIndex>(
+        &mut self,
+        iter: T,
+        index: &I,
+        min_score: Score,
+    ) -> &Vec<String> {
+        if self.generation != index.generation() {
+            self.words_cache.clear();
+            self.generation = index.generation();
         }
+
+        let key = cache_key(iter.clone(), min_score);
+
+        self.words_cache
+            .entry(key)
+            .or_insert_with(|| index.words_with_min_score(iter, min_score))
     }
+}
 
-    pub fn words<T: Iterator<Item = char> + Clone>(
+/// Caches `WordIndex::count_matches`, which ranks slots without collecting their
+/// words.
+#[derive(Clone, Default)]
+pub struct CachedCounts {
+    count_cache: FxHashMap<u64, usize>,
+    generation: u64,
+}
+
+impl CachedCounts {
+    pub fn count<T: Iterator<Item = char> + Clone, I: WordIndex>(
         &mut self,
         iter: T,
-        trie: &Trie,
-    ) -> &Vec<String> {
-        let mut hasher = FxHasher::default();
-        for c in iter.clone() {
-            c.hash(&mut hasher);
+        index: &I,
+        min_score: Score,
+    ) -> usize {
+        if self.generation != index.generation() {
+            self.count_cache.clear();
+            self.generation = index.generation();
         }
-        let key = hasher.finish();
 
-        self.words_cache
+        let key = cache_key(iter.clone(), min_score);
+
+        *self
+            .count_cache
             .entry(key)
-            .or_insert_with(|| trie.words(iter))
+            .or_insert_with(|| index.count_matches(iter, min_score))
     }
 }
 
-#[derive(Clone)]
-pub struct CachedIsViable {
-    is_viable_cache: FxHashMap<u64, bool>,
+/// Caches the highest score among the matches of a pattern, which bounds the score
+/// a slot can contribute to a fill.
+#[derive(Clone, Default)]
+pub struct CachedBestScores {
+    best_score_cache: FxHashMap<u64, Option<Score>>,
+    generation: u64,
 }
 
-impl CachedIsViable {
-    pub fn default() -> CachedIsViable {
-        CachedIsViable {
-            is_viable_cache: FxHashMap::default(),
+impl CachedBestScores {
+    pub fn best_score<T: Iterator<Item = char> + Clone, I: WordIndex>(
+        &mut self,
+        iter: T,
+        index: &I,
+        min_score: Score,
+    ) -> Option<Score> {
+        if self.generation != index.generation() {
+            self.best_score_cache.clear();
+            self.generation = index.generation();
         }
+
+        let key = cache_key(iter.clone(), min_score);
+
+        *self.best_score_cache.entry(key).or_insert_with(|| {
+            let mut best = None;
+            index.visit_matches(iter, min_score, |_, score| {
+                best = best.max(Some(score));
+                true
+            });
+            best
+        })
     }
+}
+
+/// Caches `WordIndex::masked_letter_options`, which keeps fills arc consistent.
+#[derive(Clone, Default)]
+pub struct CachedMaskedOptions {
+    masked_options_cache: FxHashMap<u64, Rc<LetterOptions>>,
+    generation: u64,
+}
 
-    pub fn is_viable<T: Iterator<Item = char> + Clone>(&mut self, iter: T, trie: &Trie) -> bool {
-        let mut hasher = FxHasher::default();
-        for c in iter.clone() {
-            c.hash(&mut hasher);
+impl CachedMaskedOptions {
+    pub fn masked_letter_options<I: WordIndex>(
+        &mut self,
+        masks: &[u64],
+        index: &I,
+        min_score: Score,
+    ) -> Rc<LetterOptions> {
+        if self.generation != index.generation() {
+            self.masked_options_cache.clear();
+            self.generation = index.generation();
         }
-        let key = hasher.finish();
+
+        let key = cache_key(masks.iter(), min_score);
+
+        self.masked_options_cache
+            .entry(key)
+            .or_insert_with(|| Rc::new(index.masked_letter_options(masks, min_score)))
+            .clone()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct CachedIsViable {
+    is_viable_cache: FxHashMap<u64, bool>,
+    letter_options_cache: FxHashMap<u64, Rc<LetterOptions>>,
+    generation: u64,
+}
+
+impl CachedIsViable {
+    pub fn is_viable<T: Iterator<Item = char> + Clone, I: WordIndex>(
+        &mut self,
+        iter: T,
+        index: &I,
+        min_score: Score,
+    ) -> bool {
+        self.follow(index);
+
+        let key = cache_key(iter.clone(), min_score);
 
         *self
             .is_viable_cache
             .entry(key)
-            .or_insert_with(|| trie.is_viable(iter))
+            .or_insert_with(|| index.is_viable_with_min_score(iter, min_score))
+    }
+
+    /// Caches `WordIndex::letter_options`.
+    pub fn letter_options<T: Iterator<Item = char> + Clone, I: WordIndex>(
+        &mut self,
+        iter: T,
+        index: &I,
+        min_score: Score,
+    ) -> Rc<LetterOptions> {
+        self.follow(index);
+
+        let key = cache_key(iter.clone(), min_score);
+
+        self.letter_options_cache
+            .entry(key)
+            .or_insert_with(|| Rc::new(index.letter_options(iter, min_score)))
+            .clone()
+    }
+
+    fn follow<I: WordIndex>(&mut self, index: &I) {
+        if self.generation != index.generation() {
+            self.is_viable_cache.clear();
+            self.letter_options_cache.clear();
+            self.generation = index.generation();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::trie::{Trie, DEFAULT_SCORE};
+
+    use super::{CachedBestScores, CachedCounts, CachedIsViable, CachedWords};
+
+    #[test]
+    fn caches_follow_changes() {
+        let mut trie = Trie::build(vec![String::from("BASS")]);
+        let mut words = CachedWords::default();
+        let mut is_viable = CachedIsViable::default();
+        let mut counts = CachedCounts::default();
+        let mut best_scores = CachedBestScores::default();
+
+        assert_eq!(words.words("B SS".chars(), &trie, 0), &vec!["BASS"]);
+        assert!(!is_viable.is_viable("BOS ".chars(), &trie, 0));
+        assert_eq!(counts.count("B SS".chars(), &trie, 0), 1);
+        assert_eq!(
+            best_scores.best_score("B SS".chars(), &trie, 0),
+            Some(DEFAULT_SCORE)
+        );
+
+        trie.insert("BOSS", DEFAULT_SCORE);
+        assert_eq!(words.words("B SS".chars(), &trie, 0), &vec!["BASS", "BOSS"]);
+        assert!(is_viable.is_viable("BOS ".chars(), &trie, 0));
+        assert!(is_viable
+            .letter_options("B SS".chars(), &trie, 0)
+            .contains(1, 'O'));
+        assert_eq!(counts.count("B SS".chars(), &trie, 0), 2);
+
+        trie.insert("BOSS", DEFAULT_SCORE + 10);
+        assert_eq!(
+            best_scores.best_score("B SS".chars(), &trie, 0),
+            Some(DEFAULT_SCORE + 10)
+        );
+        assert_eq!(
+            best_scores.best_score("B SS".chars(), &trie, DEFAULT_SCORE + 20),
+            None
+        );
+
+        trie.remove("BASS");
+        assert_eq!(words.words("B SS".chars(), &trie, 0), &vec!["BOSS"]);
+
+        let other = Trie::build(vec![String::from("BASS")]);
+        assert_eq!(words.words("B SS".chars(), &other, 0), &vec!["BASS"]);
     }
 }
@@ -3,56 +3,329 @@ An algorithm that composes algorithms and data structures throughout this
 crate. This is where the magic happens.
 */
 
-use std::{collections::HashSet, hash::BuildHasherDefault, time::Instant};
+use std::{
+    collections::HashSet,
+    convert::TryFrom,
+    hash::BuildHasherDefault,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use rustc_hash::FxHasher;
 
 use crate::{
+    bigrams::BigramModel,
     crossword::{Crossword, WordIterator},
-    parse::parse_word_boundaries,
-    trie::Trie,
+    index::WordIndex,
+    parse::{parse_word_boundaries, WordBoundary},
+    trie::{Score, Trie},
+    wordlist::tags::Tag,
 };
 
 use super::{
+    backjump::{Backjumper, Resume},
     build_square_word_boundary_lookup,
-    cache::{CachedIsViable, CachedWords},
-    fill_one_word, is_viable_reuse, words_orthogonal_to_word, Fill,
+    cache::{CachedBestScores, CachedCounts, CachedIsViable, CachedMaskedOptions, CachedWords},
+    fill_one_word, is_viable_reuse,
+    propagation::{Domains, Propagator},
+    random::SplitMix64,
+    BestFill, Crossings, Fill, Objective, ScoreThreshold, TagLimits,
 };
 
-pub struct Filler<'s> {
+/// Fills crosswords using any `WordIndex`; a `Trie` unless specified otherwise.
+pub struct Filler<'s, I = Trie> {
     word_cache: CachedWords,
+    count_cache: CachedCounts,
     is_viable_cache: CachedIsViable,
+    best_score_cache: CachedBestScores,
+    masked_options_cache: CachedMaskedOptions,
 
-    trie: &'s Trie,
+    index: &'s I,
+    min_score: ScoreThreshold,
+    tag_limits: TagLimits,
+    bigrams: Option<&'s BigramModel>,
+    objective: Option<Objective>,
+    time_limit: Option<Duration>,
+    propagate: bool,
+    backjump: bool,
+    seed: Option<u64>,
+    min_difference: usize,
+    /// The number of partial fills the last search explored.
+    explored: usize,
 }
 
-impl<'s> Filler<'s> {
-    pub fn new(trie: &'s Trie) -> Filler<'s> {
+impl<'s, I: WordIndex> Filler<'s, I> {
+    pub fn new(index: &'s I) -> Filler<'s, I> {
         Filler {
             word_cache: CachedWords::default(),
+            count_cache: CachedCounts::default(),
             is_viable_cache: CachedIsViable::default(),
-            trie,
+            best_score_cache: CachedBestScores::default(),
+            masked_options_cache: CachedMaskedOptions::default(),
+            index,
+            min_score: ScoreThreshold::default(),
+            tag_limits: TagLimits::default(),
+            bigrams: None,
+            objective: None,
+            time_limit: None,
+            propagate: false,
+            backjump: false,
+            seed: None,
+            min_difference: 1,
+            explored: 0,
+        }
+    }
+
+    /// Only fill slots with words scoring at least the given threshold.
+    pub fn with_min_score(mut self, min_score: ScoreThreshold) -> Filler<'s, I> {
+        self.min_score = min_score;
+        self
+    }
+
+    /// Use at most `max` entries tagged `tag`; see `TagLimits`.
+    pub fn with_tag_limit(mut self, tag: Tag, max: usize) -> Filler<'s, I> {
+        self.tag_limits = self.tag_limits.with_limit(tag, max);
+        self
+    }
+
+    pub fn with_tag_limits(mut self, tag_limits: TagLimits) -> Filler<'s, I> {
+        self.tag_limits = tag_limits;
+        self
+    }
+
+    /// Use `bigrams` to order the search. Among slots with equally many matches, the
+    /// one whose letters so far look least like a word is filled first, and a slot's
+    /// candidates are tried starting with the one whose crossings look most like
+    /// words.
+    pub fn with_bigrams(mut self, bigrams: &'s BigramModel) -> Filler<'s, I> {
+        self.bigrams = Some(bigrams);
+        self
+    }
+
+    /// Makes `fill` return the best fill under `objective` rather than the first one
+    /// found; see `fill_best`. Higher scoring words are tried first.
+    pub fn with_objective(mut self, objective: Objective) -> Filler<'s, I> {
+        self.objective = Some(objective);
+        self
+    }
+
+    /// Gives up after `time_limit`. An optimizing search then returns the best fill
+    /// found so far, and a search for the first fill fails.
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Filler<'s, I> {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Keeps the letters each square may still hold, and after every word placed,
+    /// narrows them until every slot is arc consistent; see `propagation`. Partial
+    /// fills that leave a square without letters are discarded before they are
+    /// explored. This explores far fewer partial fills on hard grids, but each costs
+    /// more, so grids that fill quickly without it take up to about twice as long.
+    pub fn with_propagation(mut self, propagate: bool) -> Filler<'s, I> {
+        self.propagate = propagate;
+        self
+    }
+
+    /// When every word for a slot fails, jumps back to the latest placement that the
+    /// failures depended on instead of the previous one, and remembers the letters
+    /// responsible so that partial fills containing them are not explored again; see
+    /// `backjump`.
+    pub fn with_backjumping(mut self, backjump: bool) -> Filler<'s, I> {
+        self.backjump = backjump;
+        self
+    }
+
+    /// Tries the words for each slot in a random order, in which higher scoring words
+    /// tend to come first, instead of the order the index stores them in. The same
+    /// seed, options and word list give the same fill with the same version of this
+    /// crate.
+    pub fn with_seed(mut self, seed: u64) -> Filler<'s, I> {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Requires the fills returned by `fill_distinct` to differ from each other in at
+    /// least `min_difference` entries. By default they only need to differ.
+    pub fn with_min_difference(mut self, min_difference: usize) -> Filler<'s, I> {
+        self.min_difference = min_difference;
+        self
+    }
+
+    /// The number of partial fills the last call to `fill`, `fill_best` or
+    /// `fill_distinct` explored.
+    pub fn explored(&self) -> usize {
+        self.explored
+    }
+
+    /// Fills with `index` from now on. A `Filler` borrows its index for as long as it
+    /// lives, so to fill with words added or removed since, pass it a changed copy of
+    /// the list. The caches are kept if `index` has the same `WordIndex::generation`
+    /// as the index before, and start over otherwise.
+    pub fn set_index(&mut self, index: &'s I) {
+        if index.generation() != self.index.generation() {
+            self.word_cache = CachedWords::default();
+            self.count_cache = CachedCounts::default();
+            self.is_viable_cache = CachedIsViable::default();
+            self.best_score_cache = CachedBestScores::default();
+            self.masked_options_cache = CachedMaskedOptions::default();
         }
+        self.index = index;
     }
 }
 
-impl<'s> Fill for Filler<'s> {
+/// Scales `BigramModel::score_pattern` to an integer so that it can be part of a sort
+/// key. Patterns without adjacent letters, and all patterns when there is no model,
+/// score 0.
+fn plausibility(bigrams: Option<&BigramModel>, pattern: WordIterator) -> i64 {
+    bigrams
+        .and_then(|bigrams| bigrams.score_pattern(pattern))
+        .map_or(0, |score| (score * 1000.0) as i64)
+}
+
+impl<'s, I: WordIndex> Fill for Filler<'s, I> {
+    /// Returns the first fill found, or with `with_objective`, the best one.
     fn fill(&mut self, initial_crossword: &Crossword) -> Result<Crossword, String> {
+        let deadline = self.deadline();
+        self.search(initial_crossword, self.objective, &[], deadline)
+            .map(|fill| fill.crossword)
+    }
+}
+
+impl<'s, I: WordIndex> Filler<'s, I> {
+    /// Searches for the fill that maximizes the objective set with `with_objective`,
+    /// or `Objective::TotalScore` if none was set. Partial fills that cannot beat the
+    /// best complete fill found so far are pruned, and complete fills are compared as
+    /// they are found, so that if the time limit runs out the best fill so far is
+    /// returned.
+    pub fn fill_best(&mut self, initial_crossword: &Crossword) -> Result<BestFill, String> {
+        let objective = self.objective.unwrap_or(Objective::TotalScore);
+        let deadline = self.deadline();
+        self.search(initial_crossword, Some(objective), &[], deadline)
+    }
+
+    /// Searches for up to `count` fills, each differing from every other in at least
+    /// as many entries as set with `with_min_difference`, and returns them in the
+    /// order they were found. Fewer are returned if the search, or the time limit,
+    /// runs out first, and an error if no fill is found. Objectives are not used.
+    pub fn fill_distinct(
+        &mut self,
+        initial_crossword: &Crossword,
+        count: usize,
+    ) -> Result<Vec<Crossword>, String> {
+        let deadline = self.deadline();
+        let mut fills: Vec<Crossword> = vec![];
+        let mut explored = 0;
+        while fills.len() < count.max(1) {
+            let fill = self.search(initial_crossword, None, &fills, deadline);
+            explored += self.explored;
+            match fill {
+                Ok(fill) => fills.push(fill.crossword),
+                Err(err) if fills.is_empty() => {
+                    self.explored = explored;
+                    return Err(err);
+                }
+                Err(_) => break,
+            }
+        }
+        self.explored = explored;
+        Ok(fills)
+    }
+
+    /// When the time limit set with `with_time_limit` runs out, if one was set.
+    fn deadline(&self) -> Option<Instant> {
+        self.time_limit
+            .map(|time_limit| Instant::now() + time_limit)
+    }
+
+    /// Returns the first complete fill that differs enough from every fill in
+    /// `excluded` if `objective` is `None`, and the best one otherwise. Words that an
+    /// excluded fill uses in a slot are tried last there, so that the fill found
+    /// differs from them near the root of the search too.
+    fn search(
+        &mut self,
+        initial_crossword: &Crossword,
+        objective: Option<Objective>,
+        excluded: &[Crossword],
+        deadline: Option<Instant>,
+    ) -> Result<BestFill, String> {
         let thread_start = Instant::now();
         let mut candidate_count = 0;
 
-        let word_boundaries = parse_word_boundaries(&initial_crossword);
+        let word_boundaries = parse_word_boundaries(initial_crossword);
         let mut already_used = HashSet::with_capacity_and_hasher(
             word_boundaries.len(),
             BuildHasherDefault::<FxHasher>::default(),
         );
 
-        let mut candidates = vec![initial_crossword.to_owned()];
+        let mut best: Option<BestFill> = None;
+        let min_difference = self.min_difference;
+        let differs_enough = |candidate: &Crossword| {
+            excluded.iter().all(|fill| {
+                word_boundaries.len() - matching_entries(candidate, fill, &word_boundaries)
+                    >= min_difference
+            })
+        };
 
         let word_boundary_lookup = build_square_word_boundary_lookup(&word_boundaries);
 
-        while let Some(candidate) = candidates.pop() {
+        let propagator = Propagator::new(initial_crossword, &word_boundaries);
+        let mut initial_domains = Domains::new();
+        if self.propagate {
+            initial_domains = propagator.domains(initial_crossword, self.index);
+            if !propagator.propagate_all(
+                &mut initial_domains,
+                self.index,
+                &self.min_score,
+                &mut self.masked_options_cache,
+            ) {
+                return Err(String::from("We failed"));
+            }
+        }
+        let mut candidates = vec![Candidate {
+            crossword: initial_crossword.to_owned(),
+            domains: Rc::new(initial_domains),
+            placed: None,
+            depth: 0,
+        }];
+        let mut backjumper = Backjumper::new(&propagator, initial_crossword);
+        let mut rng = self.seed.map(SplitMix64::new);
+
+        self.explored = 0;
+        while let Some(Candidate {
+            crossword: candidate,
+            mut domains,
+            placed,
+            depth,
+        }) = candidates.pop()
+        {
+            if self.backjump {
+                match backjumper.resume(depth) {
+                    Resume::Explore => {}
+                    Resume::Skip => continue,
+                    Resume::Exhausted => break,
+                }
+            }
+
+            // Propagating is costly, so it waits until a partial fill is explored
+            // rather than being done for every word tried.
+            if let (true, Some(slot)) = (self.propagate, placed) {
+                let mut new_domains = (*domains).clone();
+                let word: String = WordIterator::new(&candidate, &word_boundaries[slot]).collect();
+                let changed = propagator.place(&mut new_domains, slot, &word, self.index);
+                if !propagator.propagate(
+                    &mut new_domains,
+                    changed,
+                    self.index,
+                    &min_score_to_beat(self.min_score, objective, best.as_ref()),
+                    &mut self.masked_options_cache,
+                ) {
+                    backjumper.conflict_with_all();
+                    continue;
+                }
+                domains = Rc::new(new_domains);
+            }
             candidate_count += 1;
+            self.explored = candidate_count;
 
             if candidate_count % 10_000 == 0 {
                 println!("{}", candidate);
@@ -62,69 +335,291 @@ impl<'s> Fill for Filler<'s> {
                 );
             }
 
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return best
+                    .map(|best| BestFill {
+                        optimal: false,
+                        ..best
+                    })
+                    .ok_or_else(|| String::from("Ran out of time"));
+            }
+
+            // Fills too similar to an excluded one are not worth completing.
+            if !differs_enough(&candidate) {
+                backjumper.conflict_with_all();
+                continue;
+            }
+
+            // Only words that beat the best fill so far are worth trying.
+            if let (Some(objective), Some(best)) = (objective, &best) {
+                let bound = upper_bound(
+                    &candidate,
+                    &word_boundaries,
+                    objective,
+                    self.index,
+                    &self.min_score,
+                    &mut self.best_score_cache,
+                );
+                if bound <= best.value {
+                    backjumper.conflict_with_all();
+                    continue;
+                }
+            }
+            let min_score = min_score_to_beat(self.min_score, objective, best.as_ref());
+
             let to_fill = word_boundaries
                 .iter()
                 .map(|word_boundary| WordIterator::new(&candidate, word_boundary))
                 .filter(|iter| iter.clone().any(|c| c == ' '))
                 .min_by_key(|iter| {
+                    let min_score = min_score.for_length(iter.word_boundary.length);
                     (
-                        self.word_cache.words(iter.clone(), self.trie).len(),
+                        self.count_cache.count(iter.clone(), self.index, min_score),
+                        plausibility(self.bigrams, iter.clone()),
                         iter.word_boundary.start_row,
                         iter.word_boundary.start_col,
                     )
                 })
                 .unwrap();
 
-            let orthogonals =
-                words_orthogonal_to_word(&to_fill.word_boundary, &word_boundary_lookup);
+            let mut crossings = Crossings::new(to_fill.word_boundary, &word_boundary_lookup);
+            let slot = word_boundaries
+                .iter()
+                .position(|word_boundary| std::ptr::eq(word_boundary, to_fill.word_boundary))
+                .unwrap();
+            if self.backjump {
+                backjumper.descend(slot, &candidate);
+                // Words are held to the best fill so far, which any placement led to.
+                if best.is_some() {
+                    backjumper.conflict_with_all();
+                }
+            }
 
-            let potential_fills = self.word_cache.words(to_fill.clone(), self.trie);
+            let slot_min_score = min_score.for_length(to_fill.word_boundary.length);
+            let potential_fills =
+                self.word_cache
+                    .words(to_fill.clone(), self.index, slot_min_score);
 
-            for potential_fill in potential_fills {
-                let new_candidate = fill_one_word(&candidate, &to_fill.clone(), &potential_fill);
+            // Viable candidates and their sort keys, when they are ranked by the score
+            // of the word placed or by bigrams.
+            let index = self.index;
+            let mut order = rng.as_mut().map(|rng| {
+                rng.weighted_order(potential_fills, |word| {
+                    u64::from(index.score(word).unwrap_or_default()) + 1
+                })
+            });
+            // Words that an excluded fill uses here are tried last.
+            let used: Vec<String> = excluded
+                .iter()
+                .map(|fill| WordIterator::new(fill, to_fill.word_boundary).collect())
+                .collect();
+            if !used.is_empty() {
+                order
+                    .get_or_insert_with(|| (0..potential_fills.len()).rev().collect())
+                    .sort_by_key(|position| used.contains(&potential_fills[*position]));
+            }
+
+            let mut ranked = vec![];
+            for position in 0..potential_fills.len() {
+                // Candidates are popped from the end, so the first in `order` goes last.
+                let potential_fill = match &order {
+                    Some(order) => &potential_fills[order[order.len() - 1 - position]],
+                    None => &potential_fills[position],
+                };
+                if self.propagate && !propagator.fits(&domains, slot, potential_fill, self.index) {
+                    backjumper.conflict_with_all();
+                    continue;
+                }
+                let new_candidate = fill_one_word(&candidate, &to_fill.clone(), potential_fill);
 
                 let (viable, tmp) = is_viable_reuse(
                     &new_candidate,
-                    &orthogonals,
-                    self.trie,
+                    &mut crossings,
+                    self.index,
+                    &min_score,
                     already_used,
                     &mut self.is_viable_cache,
                 );
                 already_used = tmp;
                 already_used.clear();
 
-                if viable {
-                    if !new_candidate.contents.contains(' ') {
-                        return Ok(new_candidate);
+                if !viable {
+                    if self.backjump {
+                        backjumper.conflict_with_neighborhood();
+                    }
+                    continue;
+                }
+                if !self.tag_limits.is_empty()
+                    && !self
+                        .tag_limits
+                        .allows(&new_candidate, &word_boundaries, self.index)
+                {
+                    backjumper.conflict_with_all();
+                    continue;
+                }
+                if self.backjump && backjumper.violates_nogood(&new_candidate) {
+                    continue;
+                }
+
+                if !new_candidate.contents.contains(' ') {
+                    backjumper.conflict_with_all();
+                    let objective = match objective {
+                        Some(objective) => objective,
+                        None if differs_enough(&new_candidate) => {
+                            return Ok(BestFill {
+                                crossword: new_candidate,
+                                value: 0,
+                                optimal: false,
+                            });
+                        }
+                        None => continue,
+                    };
+                    let value = upper_bound(
+                        &new_candidate,
+                        &word_boundaries,
+                        objective,
+                        self.index,
+                        &min_score,
+                        &mut self.best_score_cache,
+                    );
+                    if best.as_ref().is_none_or(|best| value > best.value) {
+                        best = Some(BestFill {
+                            crossword: new_candidate,
+                            value,
+                            optimal: true,
+                        });
                     }
+                    continue;
+                }
+
+                let new_candidate = Candidate {
+                    crossword: new_candidate,
+                    domains: Rc::clone(&domains),
+                    placed: Some(slot),
+                    depth: depth + 1,
+                };
+                if objective.is_none() && self.bigrams.is_none() {
                     candidates.push(new_candidate);
+                    continue;
                 }
+                let score = match objective {
+                    Some(_) => self.index.score(potential_fill).unwrap_or_default(),
+                    None => 0,
+                };
+                let bigrams = self.bigrams;
+                let crossing_plausibility: i64 = crossings
+                    .word_boundaries()
+                    .iter()
+                    .map(|word_boundary| {
+                        plausibility(
+                            bigrams,
+                            WordIterator::new(&new_candidate.crossword, word_boundary),
+                        )
+                    })
+                    .sum();
+                let fresh = !used.contains(potential_fill);
+                ranked.push(((fresh, score, crossing_plausibility), new_candidate));
             }
+
+            // Candidates are popped from the end, so the most promising goes last.
+            ranked.sort_by_key(|(key, _)| *key);
+            candidates.extend(ranked.into_iter().map(|(_, candidate)| candidate));
         }
 
-        Err(String::from("We failed"))
+        best.ok_or_else(|| String::from("We failed"))
     }
 }
 
+/// Counts the slots whose entry is complete in `candidate` and the same in `fill`.
+fn matching_entries(
+    candidate: &Crossword,
+    fill: &Crossword,
+    word_boundaries: &[WordBoundary],
+) -> usize {
+    word_boundaries
+        .iter()
+        .filter(|word_boundary| {
+            let entry = WordIterator::new(candidate, word_boundary);
+            !entry.clone().any(|c| c == ' ') && entry.eq(WordIterator::new(fill, word_boundary))
+        })
+        .count()
+}
+
+/// A partial fill waiting on the search stack.
+struct Candidate {
+    crossword: Crossword,
+    /// When propagating, the domains of its parent, or its own for the initial grid,
+    /// and nothing otherwise.
+    domains: Rc<Domains>,
+    /// The slot its last word was placed in, which is yet to be propagated.
+    placed: Option<usize>,
+    /// The number of words placed since the search started.
+    depth: usize,
+}
+
+/// Raises `min_score` so that, under `Objective::MinScore`, only words that beat the
+/// weakest entry of `best` are used.
+fn min_score_to_beat(
+    min_score: ScoreThreshold,
+    objective: Option<Objective>,
+    best: Option<&BestFill>,
+) -> ScoreThreshold {
+    let mut result = min_score;
+    if let (Some(Objective::MinScore), Some(best)) = (objective, best) {
+        let better = Score::try_from(best.value + 1).unwrap_or(Score::MAX);
+        result.short = result.short.max(better);
+        result.long = result.long.max(better);
+    }
+    result
+}
+
+/// Bounds the value of any fill of `candidate` under `objective`: complete entries
+/// count with their own score, and other slots with the best score of any word that
+/// matches them. For a complete grid, this is its value.
+fn upper_bound<I: WordIndex>(
+    candidate: &Crossword,
+    word_boundaries: &[WordBoundary],
+    objective: Objective,
+    index: &I,
+    min_score: &ScoreThreshold,
+    best_score_cache: &mut CachedBestScores,
+) -> u64 {
+    objective.evaluate(word_boundaries.iter().map(|word_boundary| {
+        let iter = WordIterator::new(candidate, word_boundary);
+        if iter.clone().any(|c| c == ' ') {
+            best_score_cache
+                .best_score(iter, index, min_score.for_length(word_boundary.length))
+                .unwrap_or_default()
+        } else {
+            index.score(&iter.collect::<String>()).unwrap_or_default()
+        }
+    }))
+}
+
 #[cfg(test)]
 mod tests {
 
-    use crate::{fill::Fill, Trie};
+    use crate::{
+        bigrams::BigramModel,
+        fill::{Fill, Objective, ScoreThreshold},
+        index::bitset::BitsetIndex,
+        parse::parse_word_boundaries,
+        trie::Score,
+        wordlist::tags::{Tag, TagSet},
+        Trie,
+    };
 
     use crate::Crossword;
 
-    use std::{cmp::Ordering, time::Instant};
+    use std::{
+        cmp::Ordering,
+        time::{Duration, Instant},
+    };
 
-    use super::Filler;
+    use super::{matching_entries, Filler};
 
-    #[test]
-    fn test() {
-        assert_eq!((1, 2).cmp(&(3, 4)), Ordering::Less)
-    }
-
-    #[test]
-    fn medium_grid() {
-        let grid = Crossword::square(String::from(
+    fn medium_crossword() -> Crossword {
+        Crossword::square(String::from(
             "
     ***
     ***
@@ -135,7 +630,46 @@ mod tests {
 ***    
 ",
         ))
-        .unwrap();
+        .unwrap()
+    }
+
+    /// A 3x2 grid that `small_words` fills either with EFG and HIJ or with KLM and NOP.
+    fn small_crossword() -> Crossword {
+        Crossword::rectangle(String::from("      "), 3, 2).unwrap()
+    }
+
+    /// The entries of both fills of `small_crossword`, scored `first` and `second`.
+    fn small_words(first: Score, second: Score) -> Vec<(String, Score)> {
+        ["efg", "hij", "eh", "fi", "gj"]
+            .iter()
+            .map(|word| (String::from(*word), first))
+            .chain(
+                ["klm", "nop", "kn", "lo", "mp"]
+                    .iter()
+                    .map(|word| (String::from(*word), second)),
+            )
+            .collect()
+    }
+
+    /// Words for a 3x3 grid in which every across entry fits but no word fits the last
+    /// column.
+    fn dead_end_trie() -> Trie {
+        Trie::build(
+            ["abc", "def", "ghi", "adg", "beh", "abd", "xyz"]
+                .iter()
+                .map(|word| String::from(*word))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!((1, 2).cmp(&(3, 4)), Ordering::Less)
+    }
+
+    #[test]
+    fn medium_grid() {
+        let grid = medium_crossword();
 
         let now = Instant::now();
         let trie = Trie::load_default().expect("Failed to load trie");
@@ -144,4 +678,241 @@ mod tests {
         println!("Filled in {} seconds.", now.elapsed().as_secs());
         println!("{}", filled_puz);
     }
+
+    #[test]
+    fn min_score_works() {
+        let grid = Crossword::square(String::from("    ")).unwrap();
+        let trie = Trie::build_scored(vec![
+            (String::from("ab"), 10),
+            (String::from("cd"), 10),
+            (String::from("ac"), 10),
+            (String::from("bd"), 10),
+        ]);
+
+        assert!(Filler::new(&trie).fill(&grid).is_ok());
+        assert!(Filler::new(&trie)
+            .with_min_score(ScoreThreshold::uniform(20))
+            .fill(&grid)
+            .is_err());
+        assert!(Filler::new(&trie)
+            .with_min_score(ScoreThreshold {
+                short: 0,
+                long: 20,
+                long_length: 3,
+            })
+            .fill(&grid)
+            .is_ok());
+    }
+
+    #[test]
+    fn tag_limits_work() {
+        let grid = Crossword::square(String::from("    ")).unwrap();
+        let abbreviation = TagSet::default().with(Tag::Abbreviation);
+        let trie = Trie::build_tagged(
+            ["ab", "cd", "ac", "bd"]
+                .iter()
+                .map(|word| (String::from(*word), 50, abbreviation))
+                .collect(),
+        );
+
+        // Every entry of every fill is an abbreviation.
+        assert!(Filler::new(&trie)
+            .with_tag_limit(Tag::Abbreviation, 4)
+            .fill(&grid)
+            .is_ok());
+        assert!(Filler::new(&trie)
+            .with_tag_limit(Tag::Abbreviation, 3)
+            .fill(&grid)
+            .is_err());
+        assert!(Filler::new(&trie)
+            .with_tag_limit(Tag::Partial, 0)
+            .fill(&grid)
+            .is_ok());
+    }
+
+    #[test]
+    fn bigrams_work() {
+        let grid = medium_crossword();
+        let trie = Trie::load_default().expect("Failed to load trie");
+        let bigrams = BigramModel::load_default().unwrap();
+
+        let filled = Filler::new(&trie)
+            .with_bigrams(&bigrams)
+            .fill(&grid)
+            .unwrap();
+        assert!(!filled.contents.contains(' '));
+    }
+
+    #[test]
+    fn objectives_work() {
+        let grid = small_crossword();
+        // Either set of words fills the grid on its own: one with the best total, the
+        // other with the best weakest entry.
+        let mut words = small_words(50, 95);
+        words.last_mut().unwrap().1 = 5;
+        let trie = Trie::build_scored(words);
+
+        let best = Filler::new(&trie)
+            .with_objective(Objective::TotalScore)
+            .fill_best(&grid)
+            .unwrap();
+        assert_eq!(best.value, 385);
+        assert!(best.optimal);
+        assert_eq!(best.crossword.to_string(), "KLM\nNOP\n");
+
+        let best = Filler::new(&trie)
+            .with_objective(Objective::MinScore)
+            .fill_best(&grid)
+            .unwrap();
+        assert_eq!(best.value, 50);
+        assert_eq!(best.crossword.to_string(), "EFG\nHIJ\n");
+
+        let filled = Filler::new(&trie)
+            .with_objective(Objective::MinScore)
+            .fill(&grid)
+            .unwrap();
+        assert_eq!(filled, best.crossword);
+
+        assert!(Filler::new(&trie)
+            .with_objective(Objective::TotalScore)
+            .with_time_limit(Duration::from_secs(0))
+            .fill_best(&grid)
+            .is_err());
+    }
+
+    #[test]
+    fn propagation_works() {
+        let grid = medium_crossword();
+        let trie = Trie::load_default().expect("Failed to load trie");
+
+        let mut plain = Filler::new(&trie);
+        plain.fill(&grid).unwrap();
+        let mut propagating = Filler::new(&trie).with_propagation(true);
+        let filled = propagating.fill(&grid).unwrap();
+        assert!(!filled.contents.contains(' '));
+        assert!(propagating.explored() <= plain.explored());
+
+        // No word fits the last column, which propagation finds before placing any.
+        let grid = Crossword::square(String::from("         ")).unwrap();
+        let trie = dead_end_trie();
+        let mut filler = Filler::new(&trie).with_propagation(true);
+        assert!(filler.fill(&grid).is_err());
+        assert_eq!(filler.explored(), 0);
+    }
+
+    #[test]
+    fn backjumping_works() {
+        let grid = medium_crossword();
+        let trie = Trie::load_default().expect("Failed to load trie");
+
+        // Jumps only skip words that cannot lead to a fill, so the first fill found is
+        // the same.
+        let mut plain = Filler::new(&trie);
+        let expected = plain.fill(&grid).unwrap();
+        let mut backjumping = Filler::new(&trie).with_backjumping(true);
+        assert_eq!(backjumping.fill(&grid).unwrap(), expected);
+        assert!(backjumping.explored() <= plain.explored());
+
+        let grid = Crossword::square(String::from("         ")).unwrap();
+        let trie = dead_end_trie();
+        assert!(Filler::new(&trie)
+            .with_backjumping(true)
+            .fill(&grid)
+            .is_err());
+    }
+
+    #[test]
+    fn seeds_work() {
+        let grid = small_crossword();
+        let words = small_words(50, 90);
+        let trie = Trie::build_scored(words.clone());
+        let index = BitsetIndex::build_scored(words);
+
+        // Fills depend on the seed, and not on how the index stores its words.
+        let mut fills = vec![];
+        for seed in 0..20 {
+            let filled = Filler::new(&trie).with_seed(seed).fill(&grid).unwrap();
+            assert_eq!(
+                Filler::new(&index).with_seed(seed).fill(&grid).unwrap(),
+                filled
+            );
+            fills.push(filled.to_string());
+        }
+        assert!(fills.contains(&String::from("EFG\nHIJ\n")));
+        assert!(fills.contains(&String::from("KLM\nNOP\n")));
+
+        let mut filler = Filler::new(&trie).with_seed(7);
+        assert_eq!(filler.fill(&grid).unwrap().to_string(), fills[7]);
+        assert_eq!(filler.fill(&grid).unwrap().to_string(), fills[7]);
+    }
+
+    #[test]
+    fn distinct_fills_work() {
+        let grid = small_crossword();
+        let trie = Trie::build_scored(small_words(50, 50));
+
+        // The grid has only two fills, which share no entries.
+        let fills = Filler::new(&trie).fill_distinct(&grid, 5).unwrap();
+        assert_eq!(fills.len(), 2);
+        assert_ne!(fills[0], fills[1]);
+        let fills = Filler::new(&trie)
+            .with_min_difference(6)
+            .fill_distinct(&grid, 5)
+            .unwrap();
+        assert_eq!(fills.len(), 1);
+
+        let grid = medium_crossword();
+        let word_boundaries = parse_word_boundaries(&grid);
+        let trie = Trie::load_default().expect("Failed to load trie");
+
+        let fills = Filler::new(&trie)
+            .with_backjumping(true)
+            .with_min_difference(4)
+            .fill_distinct(&grid, 3)
+            .unwrap();
+        assert_eq!(fills.len(), 3);
+        assert_eq!(fills[0], Filler::new(&trie).fill(&grid).unwrap());
+        for (position, fill) in fills.iter().enumerate() {
+            assert!(!fill.contents.contains(' '));
+            for other in fills[..position].iter() {
+                assert!(
+                    word_boundaries.len() - matching_entries(fill, other, &word_boundaries) >= 4
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn set_index_works() {
+        let grid = small_crossword();
+        let mut words = small_words(50, 50);
+        words.retain(|(word, _)| word != "gj" && word != "mp");
+        let trie = Trie::build_scored(words);
+
+        let mut filler = Filler::new(&trie);
+        assert!(filler.fill(&grid).is_err());
+
+        let mut changed = trie.clone();
+        changed.insert("mp", 50);
+        filler.set_index(&changed);
+        assert_eq!(filler.fill(&grid).unwrap().to_string(), "KLM\nNOP\n");
+    }
+
+    #[test]
+    fn bitset_index_works() {
+        let grid = Crossword::square(String::from("    ")).unwrap();
+        let index = BitsetIndex::build_scored(vec![
+            (String::from("ab"), 10),
+            (String::from("cd"), 10),
+            (String::from("ac"), 20),
+            (String::from("bd"), 20),
+        ]);
+
+        let filled = Filler::new(&index).fill(&grid).unwrap();
+        assert_eq!(filled.to_string(), "AB\nCD\n");
+        assert!(Filler::new(&index)
+            .with_min_score(ScoreThreshold::uniform(20))
+            .fill(&grid)
+            .is_err());
+    }
 }
@@ -5,8 +5,10 @@ Utility methods consumed in filling a crossword puzzle.
 use crate::{
     crossword::{Direction, WordIterator},
     fill::cache::CachedIsViable,
+    index::WordIndex,
     parse::WordBoundary,
-    trie::Trie,
+    trie::Score,
+    wordlist::tags::Tag,
     Crossword, FxHashMap,
 };
 
@@ -14,8 +16,11 @@ use core::hash::{BuildHasherDefault, Hash};
 use rustc_hash::{FxHashSet, FxHasher};
 use std::{collections, hash::Hasher};
 
+pub mod backjump;
 pub mod cache;
 pub mod filler;
+pub mod propagation;
+pub mod random;
 
 /// The sole trait involved in filling crossword puzzles. Algorithms that
 /// conform to this interface will be easy to compare against the existing
@@ -24,6 +29,217 @@ pub trait Fill {
     fn fill(&mut self, crossword: &Crossword) -> Result<Crossword, String>;
 }
 
+/// The minimum score a word must have to be used in a fill. Constructors tend to
+/// tolerate weaker short entries than long ones, so the threshold depends on the
+/// length of the slot.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ScoreThreshold {
+    /// The minimum score for slots shorter than `long_length`.
+    pub short: Score,
+    /// The minimum score for slots of at least `long_length` squares.
+    pub long: Score,
+    pub long_length: usize,
+}
+
+impl ScoreThreshold {
+    /// A threshold that applies to every slot regardless of length.
+    pub fn uniform(min_score: Score) -> ScoreThreshold {
+        ScoreThreshold {
+            short: min_score,
+            long: min_score,
+            long_length: 0,
+        }
+    }
+
+    pub fn for_length(&self, length: usize) -> Score {
+        if length >= self.long_length {
+            self.long
+        } else {
+            self.short
+        }
+    }
+}
+
+impl Default for ScoreThreshold {
+    fn default() -> ScoreThreshold {
+        ScoreThreshold::uniform(0)
+    }
+}
+
+/// Caps on how many entries with a given tag a fill may use, e.g. no partials and at
+/// most two abbreviations. Tags come from `WordIndex::tags`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct TagLimits {
+    limits: Vec<(Tag, usize)>,
+}
+
+impl TagLimits {
+    pub fn new() -> TagLimits {
+        TagLimits::default()
+    }
+
+    /// Allows at most `max` entries tagged `tag`, replacing any earlier limit for it.
+    pub fn with_limit(mut self, tag: Tag, max: usize) -> TagLimits {
+        self.limits.retain(|(limited, _)| *limited != tag);
+        self.limits.push((tag, max));
+        self
+    }
+
+    pub fn limit(&self, tag: Tag) -> Option<usize> {
+        self.limits
+            .iter()
+            .find(|(limited, _)| *limited == tag)
+            .map(|(_, max)| *max)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.limits.is_empty()
+    }
+
+    /// Returns whether the complete entries of `candidate` stay within every limit.
+    /// Partially filled entries are not counted.
+    pub fn allows<I: WordIndex>(
+        &self,
+        candidate: &Crossword,
+        word_boundaries: &[WordBoundary],
+        index: &I,
+    ) -> bool {
+        let mut counts = vec![0; self.limits.len()];
+        for word_boundary in word_boundaries {
+            let iter = WordIterator::new(candidate, word_boundary);
+            if iter.clone().any(|c| c == ' ') {
+                continue;
+            }
+            let tags = index.tags(&iter.collect::<String>());
+            if tags.is_empty() {
+                continue;
+            }
+            for (count, (tag, max)) in counts.iter_mut().zip(self.limits.iter()) {
+                if tags.contains(*tag) {
+                    *count += 1;
+                    if *count > *max {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+/// What `Filler::with_objective` maximizes, given the score of each entry of a fill.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Objective {
+    /// The sum of the entries' scores.
+    TotalScore,
+    /// The score of the weakest entry.
+    MinScore,
+}
+
+impl Objective {
+    /// Combines entry scores into the value being maximized. A grid without entries is
+    /// worth 0.
+    pub fn evaluate<T: IntoIterator<Item = Score>>(&self, scores: T) -> u64 {
+        let scores = scores.into_iter().map(u64::from);
+        match self {
+            Objective::TotalScore => scores.sum(),
+            Objective::MinScore => scores.min().unwrap_or(0),
+        }
+    }
+}
+
+/// The best fill an optimizing search found.
+#[derive(Debug, Clone)]
+pub struct BestFill {
+    pub crossword: Crossword,
+    /// The fill's value under the `Objective` it was found with.
+    pub value: u64,
+    /// Whether the search finished, proving no better fill exists, rather than
+    /// running out of time.
+    pub optimal: bool,
+}
+
+/// The slots crossing a slot that is being filled, along with the letters allowed at
+/// each of their squares by the slot crossing that square in turn. Those slots are
+/// parallel to the slot being filled, so the letters they allow do not depend on the
+/// word it is filled with; they are looked up once, when first needed, and reused for
+/// every word tried.
+pub struct Crossings<'s> {
+    to_fill: &'s WordBoundary,
+    word_boundaries: Vec<&'s WordBoundary>,
+    word_boundary_lookup: &'s FxHashMap<(Direction, usize, usize), &'s WordBoundary>,
+    /// For each square of each crossing slot, a bitmask over the alphabet, or 0 if it
+    /// has not been looked up yet.
+    allowed: Vec<Vec<u64>>,
+}
+
+impl<'s> Crossings<'s> {
+    pub fn new(
+        to_fill: &'s WordBoundary,
+        word_boundary_lookup: &'s FxHashMap<(Direction, usize, usize), &'s WordBoundary>,
+    ) -> Crossings<'s> {
+        let word_boundaries = words_orthogonal_to_word(to_fill, word_boundary_lookup);
+        Crossings {
+            to_fill,
+            allowed: word_boundaries
+                .iter()
+                .map(|word_boundary| vec![0; word_boundary.length])
+                .collect(),
+            word_boundaries,
+            word_boundary_lookup,
+        }
+    }
+
+    pub fn word_boundaries(&self) -> &[&'s WordBoundary] {
+        &self.word_boundaries
+    }
+
+    /// Returns the letters allowed at `position` of crossing slot `slot` by the slot
+    /// that crosses it there, as a bitmask over the alphabet. Squares on the slot
+    /// being filled, and squares without a crossing slot, allow every letter.
+    fn allowed<I: WordIndex>(
+        &mut self,
+        slot: usize,
+        position: usize,
+        candidate: &Crossword,
+        index: &I,
+        min_score: &ScoreThreshold,
+        is_viable_cache: &mut CachedIsViable,
+    ) -> u64 {
+        if self.allowed[slot][position] != 0 {
+            return self.allowed[slot][position];
+        }
+
+        let word_boundary = self.word_boundaries[slot];
+        let (row, col) = match word_boundary.direction {
+            Direction::Across => (word_boundary.start_row, word_boundary.start_col + position),
+            Direction::Down => (word_boundary.start_row + position, word_boundary.start_col),
+        };
+        let allowed =
+            match self
+                .word_boundary_lookup
+                .get(&(self.to_fill.direction.clone(), row, col))
+            {
+                Some(crossing) if *crossing != self.to_fill => {
+                    let crossing_position = match crossing.direction {
+                        Direction::Across => col - crossing.start_col,
+                        Direction::Down => row - crossing.start_row,
+                    };
+                    is_viable_cache
+                        .letter_options(
+                            WordIterator::new(candidate, crossing),
+                            index,
+                            min_score.for_length(crossing.length),
+                        )
+                        .mask(crossing_position)
+                }
+                _ => u64::MAX,
+            };
+        self.allowed[slot][position] = allowed;
+        allowed
+    }
+}
+
 /// Determines whether a given crossword puzzle is viable. This performs several
 /// checks to decide whether a partially complete crossword should be considered
 /// for further filling, or should be discarded.
@@ -32,15 +248,19 @@ pub trait Fill {
 /// between calls. Currently this method is fairly hot.
 ///
 /// Viability checks include: (1) is there at least one valid word that matches this partial
-/// fill; (2) does this crossword include any repeated complete words.
-pub fn is_viable_reuse(
+/// fill scoring at least `min_score`; (2) does this crossword include any repeated
+/// complete words; (3) can each blank square of a partial fill take a letter that
+/// both its words and the words of the slot crossing that square allow.
+pub fn is_viable_reuse<I: WordIndex>(
     candidate: &Crossword,
-    word_boundaries: &[&WordBoundary],
-    trie: &Trie,
+    crossings: &mut Crossings,
+    index: &I,
+    min_score: &ScoreThreshold,
     mut already_used: FxHashSet<u64>,
     is_viable_cache: &mut CachedIsViable,
 ) -> (bool, FxHashSet<u64>) {
-    for word_boundary in word_boundaries {
+    for slot in 0..crossings.word_boundaries.len() {
+        let word_boundary = crossings.word_boundaries[slot];
         let iter = WordIterator::new(candidate, word_boundary);
 
         let mut hasher = FxHasher::default();
@@ -56,9 +276,36 @@ pub fn is_viable_reuse(
         }
         already_used.insert(key);
 
-        if !is_viable_cache.is_viable(iter, trie) {
+        let word_min_score = min_score.for_length(word_boundary.length);
+        if full {
+            if !is_viable_cache.is_viable(iter, index, word_min_score) {
+                return (false, already_used);
+            }
+            continue;
+        }
+
+        // Some word matches a partial fill exactly when every position has a possible
+        // letter.
+        let options = is_viable_cache.letter_options(iter.clone(), index, word_min_score);
+        if options.is_dead() {
             return (false, already_used);
         }
+        for (position, c) in iter.enumerate() {
+            if c == ' '
+                && options.mask(position)
+                    & crossings.allowed(
+                        slot,
+                        position,
+                        candidate,
+                        index,
+                        min_score,
+                        is_viable_cache,
+                    )
+                    == 0
+            {
+                return (false, already_used);
+            }
+        }
     }
     (true, already_used)
 }
@@ -107,9 +354,9 @@ pub fn fill_one_word(candidate: &Crossword, iter: &WordIterator, word: &str) ->
     }
 }
 
-pub fn build_square_word_boundary_lookup<'s>(
-    word_boundaries: &'s [WordBoundary],
-) -> FxHashMap<(Direction, usize, usize), &'s WordBoundary> {
+pub fn build_square_word_boundary_lookup(
+    word_boundaries: &[WordBoundary],
+) -> FxHashMap<(Direction, usize, usize), &WordBoundary> {
     let mut result = FxHashMap::default();
 
     for word_boundary in word_boundaries {
@@ -212,7 +459,7 @@ ghi
                         direction: Direction::Across,
                     },
                 ),
-                &String::from("cat")
+                &String::from("CAT")
             ),
             Crossword::square(String::from(
                 "
@@ -236,7 +483,7 @@ ghi
                         direction: Direction::Down,
                     }
                 ),
-                &String::from("cat"),
+                &String::from("CAT"),
             ),
             Crossword::square(String::from(
                 "
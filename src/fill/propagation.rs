@@ -0,0 +1,253 @@
+/*!
+Arc consistency for partially filled grids.
+
+Every square has a domain: the letters it may still hold, as a bitmask over the
+alphabet. A slot is consistent when every letter in the domain of each of its
+squares is used by some word that fits the domains of all its squares. Revising a
+slot narrows its squares' domains to the letters such words use; when a square
+narrows, the slot crossing it is revised in turn, AC-3 style, until nothing
+changes or some square has no letters left, which means the grid cannot be filled.
+
+`is_viable_reuse` only looks at the slots crossing the word just placed, so it
+misses dead ends that are two crossings away. Propagation finds them as soon as the
+word is placed.
+*/
+
+use std::collections::VecDeque;
+
+use crate::{
+    crossword::{Crossword, Direction},
+    index::WordIndex,
+    parse::WordBoundary,
+};
+
+use super::{cache::CachedMaskedOptions, ScoreThreshold};
+
+/// The domain of every square of a grid, indexed like `Crossword::contents`.
+pub type Domains = Vec<u64>;
+
+/// Propagates domains over the slots of one grid layout.
+pub struct Propagator<'s> {
+    word_boundaries: &'s [WordBoundary],
+    /// The squares of each slot.
+    squares: Vec<Vec<usize>>,
+    /// The slots through each square.
+    slots: Vec<Vec<usize>>,
+}
+
+impl<'s> Propagator<'s> {
+    pub fn new(crossword: &Crossword, word_boundaries: &'s [WordBoundary]) -> Propagator<'s> {
+        let mut slots = vec![vec![]; crossword.contents.len()];
+        let squares = word_boundaries
+            .iter()
+            .enumerate()
+            .map(|(slot, word_boundary)| {
+                (0..word_boundary.length)
+                    .map(|offset| {
+                        let square = match word_boundary.direction {
+                            Direction::Across => {
+                                word_boundary.start_row * crossword.width
+                                    + word_boundary.start_col
+                                    + offset
+                            }
+                            Direction::Down => {
+                                (word_boundary.start_row + offset) * crossword.width
+                                    + word_boundary.start_col
+                            }
+                        };
+                        slots[square].push(slot);
+                        square
+                    })
+                    .collect()
+            })
+            .collect();
+        Propagator {
+            word_boundaries,
+            squares,
+            slots,
+        }
+    }
+
+    /// The domains of `crossword` before any propagation: filled squares allow their
+    /// letter, and blank squares allow every letter of the index's alphabet.
+    pub fn domains<I: WordIndex>(&self, crossword: &Crossword, index: &I) -> Domains {
+        let alphabet = index.normalizer().alphabet();
+        let all = u64::MAX >> (64 - alphabet.letters().len());
+        crossword
+            .contents
+            .chars()
+            .map(|c| match c {
+                ' ' => all,
+                c => alphabet.index_of(c).map_or(0, |letter| 1 << letter),
+            })
+            .collect()
+    }
+
+    /// The squares of the slot `word_boundaries[slot]`.
+    pub fn squares(&self, slot: usize) -> &[usize] {
+        &self.squares[slot]
+    }
+
+    /// The number of slots in the grid.
+    pub fn slot_count(&self) -> usize {
+        self.squares.len()
+    }
+
+    /// The slots through `square`.
+    pub fn slots_through(&self, square: usize) -> &[usize] {
+        &self.slots[square]
+    }
+
+    /// Returns whether every letter of `word` is in the domain of its square of
+    /// `slot`.
+    pub fn fits<I: WordIndex>(
+        &self,
+        domains: &Domains,
+        slot: usize,
+        word: &str,
+        index: &I,
+    ) -> bool {
+        let alphabet = index.normalizer().alphabet();
+        self.squares[slot]
+            .iter()
+            .zip(word.chars())
+            .all(|(square, c)| {
+                alphabet
+                    .index_of(c)
+                    .is_some_and(|letter| domains[*square] & (1 << letter) != 0)
+            })
+    }
+
+    /// Narrows `domains` to the letters that `word` puts in the squares of `slot`, and
+    /// returns the slots crossing it whose squares changed.
+    pub fn place<I: WordIndex>(
+        &self,
+        domains: &mut Domains,
+        slot: usize,
+        word: &str,
+        index: &I,
+    ) -> Vec<usize> {
+        let alphabet = index.normalizer().alphabet();
+        let mut changed = vec![];
+        for (square, c) in self.squares[slot].iter().zip(word.chars()) {
+            let domain = alphabet.index_of(c).map_or(0, |letter| 1 << letter);
+            if domains[*square] != domain {
+                domains[*square] = domain;
+                changed.extend(self.slots[*square].iter().filter(|other| **other != slot));
+            }
+        }
+        changed
+    }
+
+    /// Revises `slots`, and then every slot through a square whose domain narrows,
+    /// until the domains are arc consistent. Returns false if some square is left
+    /// without letters.
+    pub fn propagate<I: WordIndex>(
+        &self,
+        domains: &mut Domains,
+        slots: Vec<usize>,
+        index: &I,
+        min_score: &ScoreThreshold,
+        cache: &mut CachedMaskedOptions,
+    ) -> bool {
+        let mut queued = vec![false; self.word_boundaries.len()];
+        let mut queue = VecDeque::with_capacity(slots.len());
+        for slot in slots {
+            if !queued[slot] {
+                queued[slot] = true;
+                queue.push_back(slot);
+            }
+        }
+
+        let mut masks = vec![];
+        while let Some(slot) = queue.pop_front() {
+            queued[slot] = false;
+            masks.clear();
+            masks.extend(self.squares[slot].iter().map(|square| domains[*square]));
+            let min_score = min_score.for_length(self.word_boundaries[slot].length);
+            let options = cache.masked_letter_options(&masks, index, min_score);
+            if options.is_dead() {
+                return false;
+            }
+
+            for (position, square) in self.squares[slot].iter().enumerate() {
+                if options.mask(position) == masks[position] {
+                    continue;
+                }
+                domains[*square] = options.mask(position);
+                for other in self.slots[*square].iter() {
+                    if *other != slot && !queued[*other] {
+                        queued[*other] = true;
+                        queue.push_back(*other);
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Makes every slot arc consistent.
+    pub fn propagate_all<I: WordIndex>(
+        &self,
+        domains: &mut Domains,
+        index: &I,
+        min_score: &ScoreThreshold,
+        cache: &mut CachedMaskedOptions,
+    ) -> bool {
+        self.propagate(
+            domains,
+            (0..self.word_boundaries.len()).collect(),
+            index,
+            min_score,
+            cache,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        crossword::Crossword, fill::cache::CachedMaskedOptions, fill::ScoreThreshold,
+        parse::parse_word_boundaries, trie::Trie,
+    };
+
+    use super::Propagator;
+
+    #[test]
+    fn propagation_works() {
+        let grid = Crossword::square(String::from("         ")).unwrap();
+        let word_boundaries = parse_word_boundaries(&grid);
+        let trie = Trie::build(
+            ["abc", "def", "ghi", "adg", "beh", "cfi", "abd", "xyz"]
+                .iter()
+                .map(|word| String::from(*word))
+                .collect(),
+        );
+        let propagator = Propagator::new(&grid, &word_boundaries);
+        let mut cache = CachedMaskedOptions::default();
+        let threshold = ScoreThreshold::default();
+
+        let mut domains = propagator.domains(&grid, &trie);
+        assert!(propagator.propagate_all(&mut domains, &trie, &threshold, &mut cache));
+        // The top left square starts an across word and a down word whose other
+        // letters start words too, which only A does.
+        assert_eq!(domains[0], 1 << 0);
+        assert_eq!(domains[1], (1 << 1) | (1 << 3));
+        // X starts no down word, so XYZ is never used.
+        assert!(domains.iter().all(|domain| domain & (1 << 23) == 0));
+
+        let across = word_boundaries
+            .iter()
+            .position(|word_boundary| word_boundary.start_row == 0 && word_boundary.start_col == 0)
+            .unwrap();
+        let mut placed = domains.clone();
+        let changed = propagator.place(&mut placed, across, "ABD", &trie);
+        assert_eq!(changed.len(), 2);
+        // No word starts with D and ends with I, as the last column would need to.
+        assert!(!propagator.propagate(&mut placed, changed, &trie, &threshold, &mut cache));
+
+        let changed = propagator.place(&mut domains, across, "ABC", &trie);
+        assert!(propagator.propagate(&mut domains, changed, &trie, &threshold, &mut cache));
+        assert_eq!(domains[8], 1 << 8);
+    }
+}
@@ -0,0 +1,90 @@
+/*!
+A small seeded random number generator for randomized fills.
+
+`SplitMix64` is fast, has no dependencies, and gives the same sequence for a seed on
+every platform, which makes seeded fills reproducible. It is not suitable for
+anything that needs unpredictable numbers.
+*/
+
+/// The SplitMix64 generator of Steele, Lea and Flood.
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns the positions of `items` in a random order in which heavier items
+    /// tend to come first: each item draws a uniform key divided by its weight, and
+    /// the lowest keys come first. Keys are drawn in sorted order of the items, so the
+    /// result does not depend on the order they were passed in. Weights of 0 are
+    /// treated as 1.
+    pub fn weighted_order<T: Ord, F: Fn(&T) -> u64>(
+        &mut self,
+        items: &[T],
+        weight: F,
+    ) -> Vec<usize> {
+        let mut sorted: Vec<usize> = (0..items.len()).collect();
+        sorted.sort_by(|a, b| items[*a].cmp(&items[*b]));
+
+        let mut keyed: Vec<(u64, usize)> = sorted
+            .into_iter()
+            .map(|position| (self.next_u64() / weight(&items[position]).max(1), position))
+            .collect();
+        keyed.sort_by(|(a_key, a), (b_key, b)| {
+            a_key.cmp(b_key).then_with(|| items[*a].cmp(&items[*b]))
+        });
+        keyed.into_iter().map(|(_, position)| position).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SplitMix64;
+
+    #[test]
+    fn split_mix_works() {
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(rng.next_u64(), 0xE220_A839_7B1D_CDAF);
+        assert_eq!(rng.next_u64(), 0x6E78_9E6A_A1B9_65F4);
+
+        let words = ["BAT", "ASSET", "BASS", "TAB"];
+        let mut reversed = words;
+        reversed.reverse();
+        let order = SplitMix64::new(7).weighted_order(&words, |_| 1);
+        let reversed_order = SplitMix64::new(7).weighted_order(&reversed, |_| 1);
+        let ordered: Vec<_> = order.iter().map(|position| words[*position]).collect();
+        let reversed_ordered: Vec<_> = reversed_order
+            .iter()
+            .map(|position| reversed[*position])
+            .collect();
+        assert_eq!(ordered, reversed_ordered);
+
+        // Over many seeds, a word a hundred times heavier almost always comes first.
+        let first =
+            (0..100)
+                .filter(|seed| {
+                    SplitMix64::new(*seed).weighted_order(&words, |word| {
+                        if *word == "BASS" {
+                            100
+                        } else {
+                            1
+                        }
+                    })[0]
+                        == 2
+                })
+                .count();
+        assert!(first > 90);
+    }
+}
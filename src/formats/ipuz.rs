@@ -0,0 +1,263 @@
+/*!
+The JSON-based ipuz format (<http://ipuz.org>). Only the crossword kind is supported.
+*/
+
+use std::collections::BTreeMap;
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    clues::{Clue, Enumeration},
+    crossword::{Crossword, Direction},
+    parse::number_word_boundaries,
+};
+
+use super::Puzzle;
+
+const DEFAULT_BLOCK: &str = "#";
+
+fn as_usize(value: &Value) -> Option<usize> {
+    match value {
+        Value::Number(number) => number.as_u64().map(|number| number as usize),
+        Value::String(text) => text.parse().ok(),
+        _ => None,
+    }
+}
+
+fn optional_string(root: &Map<String, Value>, key: &str) -> Option<String> {
+    root.get(key).and_then(Value::as_str).map(String::from)
+}
+
+fn read_clue(direction: &Direction, value: &Value) -> Result<Clue, String> {
+    let (number, text, enumeration) = match value {
+        Value::Array(parts) if parts.len() >= 2 => (
+            parts.first().and_then(as_usize),
+            parts[1].as_str().map(String::from),
+            None,
+        ),
+        Value::Object(fields) => (
+            fields.get("number").and_then(as_usize),
+            fields.get("clue").and_then(Value::as_str).map(String::from),
+            fields
+                .get("enumeration")
+                .and_then(Value::as_str)
+                .and_then(Enumeration::parse),
+        ),
+        _ => (None, None, None),
+    };
+    match (number, text) {
+        (Some(number), Some(text)) => Ok(Clue {
+            number,
+            direction: direction.clone(),
+            text,
+            enumeration,
+        }),
+        _ => Err(format!("Malformed ipuz clue: {}", value)),
+    }
+}
+
+pub fn read(bytes: &[u8]) -> Result<Puzzle, String> {
+    let contents =
+        std::str::from_utf8(bytes).map_err(|_| String::from("ipuz file is not valid UTF-8."))?;
+    let contents = contents.trim();
+    let contents = contents
+        .strip_prefix("ipuz(")
+        .and_then(|rest| rest.strip_suffix(')'))
+        .unwrap_or(contents);
+
+    let root: Value =
+        serde_json::from_str(contents).map_err(|err| format!("Invalid ipuz JSON: {}", err))?;
+    let root = root
+        .as_object()
+        .ok_or_else(|| String::from("ipuz root must be an object."))?;
+
+    let is_crossword = root
+        .get("kind")
+        .and_then(Value::as_array)
+        .is_some_and(|kinds| {
+            kinds.iter().any(|kind| {
+                kind.as_str()
+                    .is_some_and(|kind| kind.contains("ipuz.org/crossword"))
+            })
+        });
+    if !is_crossword {
+        return Err(String::from("Only ipuz crosswords are supported."));
+    }
+
+    let dimensions = root
+        .get("dimensions")
+        .ok_or_else(|| String::from("ipuz file has no dimensions."))?;
+    let width = dimensions
+        .get("width")
+        .and_then(as_usize)
+        .ok_or_else(|| String::from("ipuz file has no width."))?;
+    let height = dimensions
+        .get("height")
+        .and_then(as_usize)
+        .ok_or_else(|| String::from("ipuz file has no height."))?;
+    let block = root
+        .get("block")
+        .and_then(Value::as_str)
+        .unwrap_or(DEFAULT_BLOCK);
+
+    let cell = |grid: Option<&Value>, row: usize, col: usize| -> Option<Value> {
+        grid?.get(row)?.get(col).cloned()
+    };
+
+    let mut contents = String::with_capacity(width * height);
+    let mut rebus = BTreeMap::new();
+    let mut circles = vec![];
+    for row in 0..height {
+        for col in 0..width {
+            let puzzle_cell = cell(root.get("puzzle"), row, col).unwrap_or(Value::Null);
+            let solution_cell = cell(root.get("solution"), row, col).unwrap_or(Value::Null);
+
+            let (puzzle_value, style) = match &puzzle_cell {
+                Value::Object(fields) => (
+                    fields.get("cell").cloned().unwrap_or(Value::Null),
+                    fields.get("style").cloned(),
+                ),
+                other => (other.clone(), None),
+            };
+            let solution_value = match &solution_cell {
+                Value::Object(fields) => fields.get("value").cloned().unwrap_or(Value::Null),
+                other => other.clone(),
+            };
+
+            if puzzle_value.is_null() || puzzle_value.as_str() == Some(block) {
+                contents.push('*');
+                continue;
+            }
+
+            if style
+                .as_ref()
+                .and_then(|style| style.get("shapebg"))
+                .and_then(Value::as_str)
+                == Some("circle")
+            {
+                circles.push((row, col));
+            }
+
+            match solution_value.as_str() {
+                Some(letters) if letters == block => contents.push('*'),
+                Some(letters) if !letters.is_empty() => {
+                    contents.push(letters.chars().next().unwrap());
+                    if letters.chars().count() > 1 {
+                        rebus.insert((row, col), String::from(letters));
+                    }
+                }
+                _ => contents.push(' '),
+            }
+        }
+    }
+
+    let mut clues = vec![];
+    if let Some(sections) = root.get("clues").and_then(Value::as_object) {
+        for (direction, key) in [(Direction::Across, "Across"), (Direction::Down, "Down")].iter() {
+            for (name, section) in sections.iter() {
+                if name.split(':').next() != Some(key) {
+                    continue;
+                }
+                for value in section.as_array().into_iter().flatten() {
+                    clues.push(read_clue(direction, value)?);
+                }
+            }
+        }
+    }
+
+    Ok(Puzzle {
+        crossword: Crossword::rectangle(contents, width, height)?,
+        rebus,
+        circles,
+        clues,
+        title: optional_string(root, "title"),
+        author: optional_string(root, "author"),
+        copyright: optional_string(root, "copyright"),
+        notes: optional_string(root, "notes"),
+    })
+}
+
+fn write_clues(puzzle: &Puzzle, direction: &Direction) -> Value {
+    puzzle
+        .clues
+        .iter()
+        .filter(|clue| clue.direction == *direction)
+        .map(|clue| {
+            let mut result = json!({
+                "number": clue.number,
+                "clue": clue.text,
+            });
+            if let Some(enumeration) = &clue.enumeration {
+                result["enumeration"] = json!(enumeration.text);
+            }
+            result
+        })
+        .collect()
+}
+
+pub fn write(puzzle: &Puzzle) -> Result<Vec<u8>, String> {
+    let crossword = &puzzle.crossword;
+
+    let mut numbers = BTreeMap::new();
+    for (number, word_boundary) in number_word_boundaries(crossword) {
+        numbers.insert((word_boundary.start_row, word_boundary.start_col), number);
+    }
+
+    let mut grid = vec![];
+    let mut solution = vec![];
+    for row in 0..crossword.height {
+        let mut grid_row = vec![];
+        let mut solution_row = vec![];
+        for col in 0..crossword.width {
+            let c = crossword.contents.as_bytes()[row * crossword.width + col] as char;
+            if c == '*' {
+                grid_row.push(json!(DEFAULT_BLOCK));
+                solution_row.push(json!(DEFAULT_BLOCK));
+                continue;
+            }
+
+            let number = numbers.get(&(row, col)).copied().unwrap_or(0);
+            if puzzle.circles.contains(&(row, col)) {
+                grid_row.push(json!({"cell": number, "style": {"shapebg": "circle"}}));
+            } else {
+                grid_row.push(json!(number));
+            }
+
+            solution_row.push(match puzzle.rebus.get(&(row, col)) {
+                Some(letters) => json!(letters),
+                None if c == ' ' => Value::Null,
+                None => json!(c.to_string()),
+            });
+        }
+        grid.push(Value::Array(grid_row));
+        solution.push(Value::Array(solution_row));
+    }
+
+    let mut root = json!({
+        "version": "http://ipuz.org/v2",
+        "kind": ["http://ipuz.org/crossword#1"],
+        "dimensions": {"width": crossword.width, "height": crossword.height},
+        "block": DEFAULT_BLOCK,
+        "empty": 0,
+        "puzzle": grid,
+        "solution": solution,
+        "clues": {
+            "Across": write_clues(puzzle, &Direction::Across),
+            "Down": write_clues(puzzle, &Direction::Down),
+        },
+    });
+    for (key, value) in [
+        ("title", &puzzle.title),
+        ("author", &puzzle.author),
+        ("copyright", &puzzle.copyright),
+        ("notes", &puzzle.notes),
+    ]
+    .iter()
+    {
+        if let Some(value) = value {
+            root[*key] = json!(value);
+        }
+    }
+
+    serde_json::to_vec_pretty(&root).map_err(|err| format!("Failed to write ipuz: {}", err))
+}
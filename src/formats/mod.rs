@@ -0,0 +1,263 @@
+/*!
+Reading and writing puzzles in common file formats.
+
+Every format is read into and written from a [`Puzzle`], so converting between two
+formats is a read followed by a write. Formats do not all support the same features;
+[`Format::lost_features`] reports what would be dropped by a given write.
+*/
+
+use std::{collections::BTreeMap, fmt, path::Path};
+
+use crate::{
+    clues::{match_clues, Clue, ClueMismatch},
+    crossword::Crossword,
+};
+
+pub mod ipuz;
+pub mod puz;
+pub mod text;
+
+/// A format-independent representation of a puzzle: a grid plus everything else a
+/// puzzle file may carry.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Puzzle {
+    /// The grid. Rebus squares hold the first letter of their rebus.
+    pub crossword: Crossword,
+    /// Squares holding more than one letter, keyed by `(row, col)`.
+    pub rebus: BTreeMap<(usize, usize), String>,
+    /// Circled squares, keyed by `(row, col)`.
+    pub circles: Vec<(usize, usize)>,
+    pub clues: Vec<Clue>,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub copyright: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl Puzzle {
+    /// Wraps a bare grid in a `Puzzle` with no clues or metadata.
+    pub fn new(crossword: Crossword) -> Puzzle {
+        Puzzle {
+            crossword,
+            rebus: BTreeMap::new(),
+            circles: vec![],
+            clues: vec![],
+            title: None,
+            author: None,
+            copyright: None,
+            notes: None,
+        }
+    }
+
+    fn has_metadata(&self) -> bool {
+        self.title.is_some() || self.author.is_some() || self.copyright.is_some()
+    }
+}
+
+/// The file formats this crate can read and write.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    /// A plain text grid, as used in `grids/`.
+    Text,
+    /// The Across Lite binary format.
+    Puz,
+    /// The JSON-based ipuz format.
+    Ipuz,
+}
+
+const PUZ_MAGIC: &[u8] = b"ACROSS&DOWN\0";
+
+impl Format {
+    /// Guesses a format from a file extension alone.
+    pub fn from_extension(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "puz" => Some(Format::Puz),
+            "ipuz" => Some(Format::Ipuz),
+            "txt" => Some(Format::Text),
+            _ => None,
+        }
+    }
+
+    /// Detects the format of a file's contents. Magic bytes take precedence over the
+    /// extension, so a mislabeled file is still read correctly.
+    pub fn detect(path: &Path, bytes: &[u8]) -> Result<Format, String> {
+        if bytes.len() >= 2 + PUZ_MAGIC.len() && &bytes[2..2 + PUZ_MAGIC.len()] == PUZ_MAGIC {
+            return Ok(Format::Puz);
+        }
+        if let Ok(contents) = std::str::from_utf8(bytes) {
+            let trimmed = contents.trim_start();
+            if (trimmed.starts_with('{') || trimmed.starts_with("ipuz("))
+                && trimmed.contains("ipuz.org")
+            {
+                return Ok(Format::Ipuz);
+            }
+        }
+        match Format::from_extension(path) {
+            Some(format) => Ok(format),
+            None if std::str::from_utf8(bytes).is_ok() => Ok(Format::Text),
+            None => Err(format!(
+                "Could not detect the format of {}.",
+                path.display()
+            )),
+        }
+    }
+
+    pub fn read(self, bytes: &[u8]) -> Result<Puzzle, String> {
+        match self {
+            Format::Text => text::read(bytes),
+            Format::Puz => puz::read(bytes),
+            Format::Ipuz => ipuz::read(bytes),
+        }
+    }
+
+    pub fn write(self, puzzle: &Puzzle) -> Result<Vec<u8>, String> {
+        match self {
+            Format::Text => Ok(text::write(puzzle)),
+            Format::Puz => puz::write(puzzle),
+            Format::Ipuz => ipuz::write(puzzle),
+        }
+    }
+
+    /// Describes each feature of `puzzle` that cannot be represented in this format.
+    pub fn lost_features(self, puzzle: &Puzzle) -> Vec<String> {
+        let mut result = vec![];
+        match self {
+            Format::Text => {
+                if !puzzle.clues.is_empty() {
+                    result.push(String::from("clues are not supported"));
+                }
+                if puzzle.has_metadata() || puzzle.notes.is_some() {
+                    result.push(String::from(
+                        "title, author, copyright and notes are not supported",
+                    ));
+                }
+                if !puzzle.rebus.is_empty() {
+                    result.push(String::from(
+                        "rebus squares are not supported; only their first letter is kept",
+                    ));
+                }
+                if !puzzle.circles.is_empty() {
+                    result.push(String::from("circled squares are not supported"));
+                }
+            }
+            Format::Puz => {
+                let unmatched = match_clues(&puzzle.crossword, &puzzle.clues)
+                    .mismatches
+                    .iter()
+                    .filter(|mismatch| matches!(mismatch, ClueMismatch::NoSuchEntry { .. }))
+                    .count();
+                if unmatched > 0 {
+                    result.push(format!(
+                        "{} clues that do not match an entry in the grid are dropped",
+                        unmatched
+                    ));
+                }
+                if puzzle.crossword.contents.contains(' ') {
+                    result.push(String::from(
+                        "unfilled squares have no solution letter and are written as '-'",
+                    ));
+                }
+                if puzzle
+                    .clues
+                    .iter()
+                    .map(|clue| clue.text.as_str())
+                    .chain(puzzle.title.iter().map(String::as_str))
+                    .chain(puzzle.author.iter().map(String::as_str))
+                    .chain(puzzle.copyright.iter().map(String::as_str))
+                    .chain(puzzle.notes.iter().map(String::as_str))
+                    .any(|text| text.chars().any(|c| c as u32 > 0xFF))
+                {
+                    result.push(String::from(
+                        "characters outside ISO-8859-1 are replaced with '?'",
+                    ));
+                }
+            }
+            Format::Ipuz => {}
+        }
+        result
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Text => write!(f, "text"),
+            Format::Puz => write!(f, "puz"),
+            Format::Ipuz => write!(f, "ipuz"),
+        }
+    }
+}
+
+/// Reads a puzzle from disk, detecting its format.
+pub fn read_puzzle(path: &Path) -> Result<Puzzle, String> {
+    let bytes =
+        std::fs::read(path).map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+    Format::detect(path, &bytes)?.read(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::{clues::parse_clues, Crossword};
+
+    use super::{Format, Puzzle};
+
+    pub(crate) fn sample_puzzle() -> Puzzle {
+        let mut puzzle = Puzzle::new(Crossword::square(String::from("CAT\nO*A\nWEB")).unwrap());
+        puzzle.clues = parse_clues(
+            "
+Across
+1. Feline pet (3)
+3. Spider's home (3)
+Down
+1. Dairy animal (3)
+2. Taxi (3)
+",
+        )
+        .unwrap();
+        puzzle.title = Some(String::from("Tiny"));
+        puzzle.author = Some(String::from("Anonymous"));
+        puzzle.circles = vec![(0, 0)];
+        puzzle.rebus.insert((2, 2), String::from("BEE"));
+        puzzle
+    }
+
+    #[test]
+    fn detect_works() {
+        assert_eq!(Format::detect(Path::new("a.puz"), b"grid"), Ok(Format::Puz),);
+        assert_eq!(Format::detect(Path::new("a"), b"grid"), Ok(Format::Text));
+        assert_eq!(
+            Format::detect(Path::new("a.txt"), b"\0\0ACROSS&DOWN\0"),
+            Ok(Format::Puz),
+        );
+        assert_eq!(
+            Format::detect(Path::new("a"), b"{\"version\": \"http://ipuz.org/v2\"}"),
+            Ok(Format::Ipuz),
+        );
+        assert_eq!(
+            Format::detect(Path::new("a.ipuz"), b"ABC"),
+            Ok(Format::Ipuz),
+        );
+    }
+
+    #[test]
+    fn round_trips_work() {
+        let puzzle = sample_puzzle();
+
+        for format in [Format::Puz, Format::Ipuz].iter() {
+            assert!(format.lost_features(&puzzle).is_empty());
+            let written = format.write(&puzzle).unwrap();
+            assert_eq!(Format::detect(Path::new("out"), &written), Ok(*format));
+            assert_eq!(format.read(&written).unwrap(), puzzle);
+        }
+
+        assert_eq!(Format::Text.lost_features(&puzzle).len(), 4);
+        let written = Format::Text.write(&puzzle).unwrap();
+        assert_eq!(
+            Format::Text.read(&written).unwrap(),
+            Puzzle::new(puzzle.crossword)
+        );
+    }
+}
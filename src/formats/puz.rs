@@ -0,0 +1,392 @@
+/*!
+The Across Lite `.puz` format.
+
+The layout follows the community documentation of the format: a fixed 52 byte header,
+the solution and player grids, null-terminated ISO-8859-1 strings, and optional
+extension sections for rebus squares (`GRBS`/`RTBL`) and circles (`GEXT`).
+*/
+
+use std::collections::BTreeMap;
+
+use crate::{
+    clues::{split_enumeration, Clue},
+    crossword::{Crossword, Direction},
+    parse::number_word_boundaries,
+};
+
+use super::{Puzzle, PUZ_MAGIC};
+
+const HEADER_LENGTH: usize = 0x34;
+const VERSION: &[u8] = b"1.3\0";
+const CIRCLED: u8 = 0x80;
+
+fn checksum_region(data: &[u8], mut checksum: u16) -> u16 {
+    for byte in data {
+        checksum = if checksum & 1 == 1 {
+            (checksum >> 1).wrapping_add(0x8000)
+        } else {
+            checksum >> 1
+        };
+        checksum = checksum.wrapping_add(*byte as u16);
+    }
+    checksum
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn decode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| *byte as char).collect()
+}
+
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| if (c as u32) <= 0xFF { c as u8 } else { b'?' })
+        .collect()
+}
+
+struct Reader<'s> {
+    bytes: &'s [u8],
+    offset: usize,
+}
+
+impl<'s> Reader<'s> {
+    fn take(&mut self, length: usize) -> Result<&'s [u8], String> {
+        if self.offset + length > self.bytes.len() {
+            return Err(String::from("Unexpected end of .puz file."));
+        }
+        let result = &self.bytes[self.offset..self.offset + length];
+        self.offset += length;
+        Ok(result)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let remaining = &self.bytes[self.offset..];
+        let end = remaining
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or_else(|| String::from("Unterminated string in .puz file."))?;
+        self.offset += end + 1;
+        Ok(decode(&remaining[..end]))
+    }
+}
+
+fn non_empty(text: String) -> Option<String> {
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// Reads a `.puz` file. Checksums are not verified, but scrambled (locked) puzzles are
+/// rejected since their solution cannot be recovered.
+pub fn read(bytes: &[u8]) -> Result<Puzzle, String> {
+    if bytes.len() < HEADER_LENGTH || &bytes[2..2 + PUZ_MAGIC.len()] != PUZ_MAGIC {
+        return Err(String::from("Not a .puz file."));
+    }
+
+    let width = bytes[0x2C] as usize;
+    let height = bytes[0x2D] as usize;
+    let clue_count = read_u16(bytes, 0x2E) as usize;
+    if width == 0 || height == 0 {
+        return Err(String::from("The grid in the .puz file is empty."));
+    }
+    if read_u16(bytes, 0x32) != 0 {
+        return Err(String::from("Scrambled .puz files are not supported."));
+    }
+
+    let mut reader = Reader {
+        bytes,
+        offset: HEADER_LENGTH,
+    };
+
+    let solution = reader.take(width * height)?;
+    reader.take(width * height)?;
+
+    let contents: String = solution
+        .iter()
+        .map(|byte| match byte {
+            b'.' => '*',
+            b'-' => ' ',
+            other => *other as char,
+        })
+        .collect();
+    let crossword = Crossword::rectangle(contents, width, height)?;
+
+    let title = reader.string()?;
+    let author = reader.string()?;
+    let copyright = reader.string()?;
+
+    let entries = number_word_boundaries(&crossword);
+    if entries.len() != clue_count {
+        return Err(format!(
+            "The grid has {} entries but the file has {} clues.",
+            entries.len(),
+            clue_count
+        ));
+    }
+    let mut clues = Vec::with_capacity(clue_count);
+    for (number, word_boundary) in entries {
+        let (text, enumeration) = split_enumeration(&reader.string()?);
+        if text.is_empty() && enumeration.is_none() {
+            continue;
+        }
+        clues.push(Clue {
+            number,
+            direction: word_boundary.direction,
+            text,
+            enumeration,
+        });
+    }
+    clues.sort_by_key(|clue| clue.direction == Direction::Down);
+
+    let notes = if reader.offset < bytes.len() {
+        reader.string()?
+    } else {
+        String::new()
+    };
+
+    let mut rebus_grid = None;
+    let mut rebus_table = BTreeMap::new();
+    let mut circles = vec![];
+    while reader.offset + 8 <= bytes.len() {
+        let name = reader.take(4)?;
+        let length = read_u16(reader.take(2)?, 0) as usize;
+        reader.take(2)?;
+        let data = reader.take(length)?;
+        reader.take(1)?;
+
+        if (name == b"GRBS" || name == b"GEXT") && data.len() != width * height {
+            return Err(format!(
+                "The {} section has {} squares but the grid has {}.",
+                decode(name),
+                data.len(),
+                width * height
+            ));
+        }
+        match name {
+            b"GRBS" => rebus_grid = Some(data),
+            b"RTBL" => {
+                for entry in decode(data).split(';') {
+                    if let Some((key, value)) = entry.split_once(':') {
+                        let key: u8 = key
+                            .trim()
+                            .parse()
+                            .map_err(|_| String::from("Malformed rebus table."))?;
+                        rebus_table.insert(key, String::from(value));
+                    }
+                }
+            }
+            b"GEXT" => {
+                for (index, flags) in data.iter().enumerate() {
+                    if flags & CIRCLED != 0 {
+                        circles.push((index / width, index % width));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut rebus = BTreeMap::new();
+    if let Some(rebus_grid) = rebus_grid {
+        for (index, key) in rebus_grid.iter().enumerate() {
+            if *key == 0 {
+                continue;
+            }
+            let value = rebus_table
+                .get(&(key - 1))
+                .ok_or_else(|| String::from("Rebus square missing from rebus table."))?;
+            rebus.insert((index / width, index % width), value.clone());
+        }
+    }
+
+    Ok(Puzzle {
+        crossword,
+        rebus,
+        circles,
+        clues,
+        title: non_empty(title),
+        author: non_empty(author),
+        copyright: non_empty(copyright),
+        notes: non_empty(notes),
+    })
+}
+
+fn push_extension(result: &mut Vec<u8>, name: &[u8], data: &[u8]) {
+    result.extend_from_slice(name);
+    result.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    result.extend_from_slice(&checksum_region(data, 0).to_le_bytes());
+    result.extend_from_slice(data);
+    result.push(0);
+}
+
+pub fn write(puzzle: &Puzzle) -> Result<Vec<u8>, String> {
+    let crossword = &puzzle.crossword;
+    if crossword.width > 0xFF || crossword.height > 0xFF {
+        return Err(String::from("Grid is too large for a .puz file."));
+    }
+
+    let solution: Vec<u8> = crossword
+        .contents
+        .chars()
+        .map(|c| match c {
+            '*' => b'.',
+            ' ' => b'-',
+            other => encode(&other.to_string())[0],
+        })
+        .collect();
+    let state: Vec<u8> = solution
+        .iter()
+        .map(|byte| if *byte == b'.' { b'.' } else { b'-' })
+        .collect();
+
+    let title = encode(puzzle.title.as_deref().unwrap_or(""));
+    let author = encode(puzzle.author.as_deref().unwrap_or(""));
+    let copyright = encode(puzzle.copyright.as_deref().unwrap_or(""));
+    let notes = encode(puzzle.notes.as_deref().unwrap_or(""));
+    let clues: Vec<Vec<u8>> = number_word_boundaries(crossword)
+        .iter()
+        .map(|(number, word_boundary)| {
+            puzzle
+                .clues
+                .iter()
+                .find(|clue| clue.number == *number && clue.direction == word_boundary.direction)
+                .map_or_else(Vec::new, |clue| match &clue.enumeration {
+                    Some(enumeration) => encode(&format!("{} {}", clue.text, enumeration)),
+                    None => encode(&clue.text),
+                })
+        })
+        .collect();
+
+    let mut header = vec![0; HEADER_LENGTH];
+    header[2..2 + PUZ_MAGIC.len()].copy_from_slice(PUZ_MAGIC);
+    header[0x18..0x1C].copy_from_slice(VERSION);
+    header[0x2C] = crossword.width as u8;
+    header[0x2D] = crossword.height as u8;
+    header[0x2E..0x30].copy_from_slice(&(clues.len() as u16).to_le_bytes());
+    header[0x30..0x32].copy_from_slice(&1u16.to_le_bytes());
+
+    let cib_checksum = checksum_region(&header[0x2C..0x34], 0);
+    let solution_checksum = checksum_region(&solution, 0);
+    let state_checksum = checksum_region(&state, 0);
+
+    let text_checksum = |mut checksum: u16| {
+        for text in [&title, &author, &copyright].iter() {
+            if !text.is_empty() {
+                checksum = checksum_region(text, checksum);
+                checksum = checksum_region(&[0], checksum);
+            }
+        }
+        for clue in clues.iter() {
+            checksum = checksum_region(clue, checksum);
+        }
+        if !notes.is_empty() {
+            checksum = checksum_region(&notes, checksum);
+            checksum = checksum_region(&[0], checksum);
+        }
+        checksum
+    };
+
+    let mut global_checksum = checksum_region(&solution, cib_checksum);
+    global_checksum = checksum_region(&state, global_checksum);
+    global_checksum = text_checksum(global_checksum);
+    let part_checksum = text_checksum(0);
+
+    let masked = b"ICHEATED";
+    let sums = [
+        cib_checksum,
+        solution_checksum,
+        state_checksum,
+        part_checksum,
+    ];
+    for (index, sum) in sums.iter().enumerate() {
+        header[0x10 + index] = masked[index] ^ (sum & 0xFF) as u8;
+        header[0x14 + index] = masked[index + 4] ^ (sum >> 8) as u8;
+    }
+    header[0..2].copy_from_slice(&global_checksum.to_le_bytes());
+    header[0x0E..0x10].copy_from_slice(&cib_checksum.to_le_bytes());
+
+    let mut result = header;
+    result.extend_from_slice(&solution);
+    result.extend_from_slice(&state);
+    for text in [&title, &author, &copyright].iter() {
+        result.extend_from_slice(text);
+        result.push(0);
+    }
+    for clue in clues.iter() {
+        result.extend_from_slice(clue);
+        result.push(0);
+    }
+    result.extend_from_slice(&notes);
+    result.push(0);
+
+    if !puzzle.rebus.is_empty() {
+        let mut keys: Vec<&String> = puzzle.rebus.values().collect();
+        keys.sort();
+        keys.dedup();
+        if keys.len() > 0xFE {
+            return Err(String::from(
+                "Too many distinct rebus entries for a .puz file.",
+            ));
+        }
+
+        let mut grid = vec![0u8; crossword.width * crossword.height];
+        for ((row, col), value) in puzzle.rebus.iter() {
+            let key = keys.iter().position(|key| *key == value).unwrap();
+            grid[row * crossword.width + col] = key as u8 + 1;
+        }
+        let table: String = keys
+            .iter()
+            .enumerate()
+            .map(|(key, value)| format!("{:2}:{};", key, value))
+            .collect();
+
+        push_extension(&mut result, b"GRBS", &grid);
+        push_extension(&mut result, b"RTBL", &encode(&table));
+    }
+
+    if !puzzle.circles.is_empty() {
+        let mut flags = vec![0u8; crossword.width * crossword.height];
+        for (row, col) in puzzle.circles.iter() {
+            flags[row * crossword.width + col] |= CIRCLED;
+        }
+        push_extension(&mut result, b"GEXT", &flags);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::tests::sample_puzzle;
+
+    use super::{push_extension, read, write};
+
+    #[test]
+    fn malformed_extensions_fail() {
+        let written = write(&sample_puzzle()).unwrap();
+        let gext = written
+            .windows(4)
+            .position(|window| window == b"GEXT")
+            .unwrap();
+
+        for squares in [8, 10].iter() {
+            let mut bytes = written[..gext].to_vec();
+            push_extension(&mut bytes, b"GEXT", &vec![0; *squares]);
+            assert_eq!(
+                read(&bytes),
+                Err(format!(
+                    "The GEXT section has {} squares but the grid has 9.",
+                    squares
+                ))
+            );
+        }
+
+        let mut empty = written;
+        empty[0x2C] = 0;
+        assert!(read(&empty).is_err());
+    }
+}
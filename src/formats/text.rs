@@ -0,0 +1,85 @@
+/*!
+The plain text grid format used in `grids/`: one line per row, `*` for shaded squares
+and ` ` for blank squares.
+*/
+
+use crate::crossword::Crossword;
+
+use super::Puzzle;
+
+/// Reads a text grid. The width is taken from the longest row, and shorter rows are
+/// padded with blank squares, since trailing spaces are easily lost when editing.
+/// Empty lines before and after the grid are ignored, but a line of spaces is a row
+/// of blank squares.
+pub fn read(bytes: &[u8]) -> Result<Puzzle, String> {
+    let contents =
+        std::str::from_utf8(bytes).map_err(|_| String::from("Text grid is not valid UTF-8."))?;
+
+    let rows: Vec<&str> = contents
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .skip_while(|line| line.is_empty())
+        .collect();
+    let height = rows
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(0, |last| last + 1);
+    let rows = &rows[..height];
+
+    let width = rows
+        .iter()
+        .map(|row| row.chars().count())
+        .max()
+        .unwrap_or(0);
+    if width == 0 {
+        return Err(String::from("Text grid is empty."));
+    }
+
+    let mut grid = String::with_capacity(width * height);
+    for row in rows {
+        grid.push_str(row);
+        for _ in row.chars().count()..width {
+            grid.push(' ');
+        }
+    }
+
+    Ok(Puzzle::new(Crossword::rectangle(grid, width, height)?))
+}
+
+pub fn write(puzzle: &Puzzle) -> Vec<u8> {
+    puzzle.crossword.to_string().into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{formats::Format, Crossword};
+
+    use super::{read, write};
+
+    #[test]
+    fn read_pads_short_rows() {
+        let puzzle = read(b"\nab*\nc\n*de\n\n").unwrap();
+        assert_eq!(
+            puzzle.crossword,
+            Crossword::rectangle(String::from("ab*c  *de"), 3, 3).unwrap()
+        );
+    }
+
+    #[test]
+    fn blank_rows_round_trip() {
+        let grids: [&[u8]; 3] = [
+            include_bytes!("../../grids/empty_4x4.txt"),
+            include_bytes!("../../grids/20201005_empty.txt"),
+            b"   \n * \n   ",
+        ];
+        for grid in grids.iter() {
+            let puzzle = read(grid).unwrap();
+            assert_eq!(puzzle.crossword.height, puzzle.crossword.width);
+            for format in [Format::Puz, Format::Ipuz].iter() {
+                let written = format.write(&puzzle).unwrap();
+                assert_eq!(format.read(&written).unwrap(), puzzle);
+            }
+            assert_eq!(read(&write(&puzzle)).unwrap(), puzzle);
+        }
+    }
+}
@@ -0,0 +1,321 @@
+/*!
+A word index that answers pattern lookups by intersecting bitsets.
+
+For every word length, position and letter, the index keeps a bitset of the words
+with that letter at that position. A pattern is matched by intersecting the bitsets
+of its filled squares, so lookups never walk the words that fill its blanks.
+*/
+
+use std::{collections::BTreeMap, io::BufRead};
+
+use crate::{
+    trie::{Score, DEFAULT_SCORE},
+    wordlist::{normalize::Normalizer, read_words, WordListFormat},
+    FxHashMap,
+};
+
+use super::{next_generation, WordIndex};
+
+/// The words of one length, sorted by descending score so that the words scoring at
+/// least some minimum always have the lowest ids.
+#[derive(Clone, Default)]
+struct LengthIndex {
+    words: Vec<String>,
+    scores: Vec<Score>,
+    /// The id of each word, for looking up scores.
+    ids: FxHashMap<String, u32>,
+    /// The number of `u64`s in each bitset.
+    chunks: usize,
+    /// The bitset for `(position, letter)` starts at
+    /// `(position * alphabet length + letter) * chunks`.
+    bits: Vec<u64>,
+}
+
+impl LengthIndex {
+    fn new(mut entries: Vec<(String, Score)>, normalizer: &Normalizer, length: usize) -> Self {
+        entries.sort_by(|(a, a_score), (b, b_score)| b_score.cmp(a_score).then(a.cmp(b)));
+
+        let letters = normalizer.alphabet().letters().len();
+        let chunks = entries.len().div_ceil(64);
+        let mut bits = vec![0; length * letters * chunks];
+        for (id, (word, _)) in entries.iter().enumerate() {
+            for (position, c) in word.chars().enumerate() {
+                let letter = normalizer
+                    .alphabet()
+                    .index_of(c)
+                    .expect("normalized words only contain letters of the alphabet");
+                bits[(position * letters + letter) * chunks + id / 64] |= 1 << (id % 64);
+            }
+        }
+
+        let ids = entries
+            .iter()
+            .enumerate()
+            .map(|(id, (word, _))| (word.clone(), id as u32))
+            .collect();
+        let (words, scores) = entries.into_iter().unzip();
+        LengthIndex {
+            words,
+            scores,
+            ids,
+            chunks,
+            bits,
+        }
+    }
+}
+
+/// An alternative to `Trie` that intersects per-position bitsets. It trades memory
+/// for lookups whose cost does not depend on where a pattern's blanks are.
+#[derive(Clone)]
+pub struct BitsetIndex {
+    normalizer: Normalizer,
+    /// Indexed by word length.
+    lengths: Vec<LengthIndex>,
+    generation: u64,
+}
+
+impl BitsetIndex {
+    /// Builds an index from unscored words, giving each of them `DEFAULT_SCORE`.
+    pub fn build(words: Vec<String>) -> BitsetIndex {
+        BitsetIndex::build_scored(
+            words
+                .into_iter()
+                .map(|word| (word, DEFAULT_SCORE))
+                .collect(),
+        )
+    }
+
+    /// Builds an index from scored words. Words are normalized with the default
+    /// `Normalizer`, and if a word appears more than once, the last score wins.
+    pub fn build_scored(words: Vec<(String, Score)>) -> BitsetIndex {
+        let normalizer = Normalizer::default();
+        let mut entries = BTreeMap::new();
+        for (word, score) in words {
+            let normalized = normalizer.normalize(&word);
+            if !normalized.is_empty() {
+                entries.insert(normalized, score);
+            }
+        }
+        BitsetIndex::from_entries(normalizer, entries)
+    }
+
+    /// Builds an index from a word list in any `WordListFormat`.
+    pub fn from_reader<R: BufRead>(
+        reader: R,
+        format: WordListFormat,
+    ) -> Result<BitsetIndex, String> {
+        let normalizer = Normalizer::default();
+        let mut entries = BTreeMap::new();
+        read_words(reader, format, |word, score| {
+            let normalized = normalizer.normalize(&word);
+            if !normalized.is_empty() {
+                entries.insert(normalized, score);
+            }
+        })?;
+        Ok(BitsetIndex::from_entries(normalizer, entries))
+    }
+
+    fn from_entries(normalizer: Normalizer, entries: BTreeMap<String, Score>) -> BitsetIndex {
+        let mut by_length: Vec<Vec<(String, Score)>> = vec![];
+        for (word, score) in entries {
+            let length = word.chars().count();
+            if by_length.len() <= length {
+                by_length.resize_with(length + 1, Vec::new);
+            }
+            by_length[length].push((word, score));
+        }
+
+        let lengths = by_length
+            .into_iter()
+            .enumerate()
+            .map(|(length, entries)| LengthIndex::new(entries, &normalizer, length))
+            .collect();
+        BitsetIndex {
+            normalizer,
+            lengths,
+            generation: next_generation(),
+        }
+    }
+
+    /// Intersects the bitsets of `pattern`'s filled squares, restricted to words
+    /// scoring at least `min_score`, and passes each non-empty chunk of the result
+    /// to `visit` along with its index. Stops early if `visit` returns false.
+    fn visit_chunks<
+        T: Iterator<Item = char> + Clone,
+        F: FnMut(&LengthIndex, usize, u64) -> bool,
+    >(
+        &self,
+        pattern: T,
+        min_score: Score,
+        mut visit: F,
+    ) {
+        let index = match self.lengths.get(pattern.clone().count()) {
+            Some(index) => index,
+            None => return,
+        };
+        let letters = self.normalizer.alphabet().letters().len();
+
+        let mut offsets = vec![];
+        for (position, c) in pattern.enumerate() {
+            if c == ' ' {
+                continue;
+            }
+            match self.normalizer.alphabet().index_of(c) {
+                Some(letter) => offsets.push((position * letters + letter) * index.chunks),
+                None => return,
+            }
+        }
+
+        let count = index.scores.partition_point(|score| *score >= min_score);
+        for chunk in 0..count.div_ceil(64) {
+            let mut bits = if (chunk + 1) * 64 <= count {
+                u64::MAX
+            } else {
+                (1 << (count % 64)) - 1
+            };
+            for offset in offsets.iter() {
+                bits &= index.bits[offset + chunk];
+            }
+            if bits != 0 && !visit(index, chunk, bits) {
+                return;
+            }
+        }
+    }
+}
+
+impl WordIndex for BitsetIndex {
+    /// Returns matching words in order of descending score.
+    fn words_with_min_score<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> Vec<String> {
+        let mut result = vec![];
+        self.visit_chunks(pattern, min_score, |index, chunk, mut bits| {
+            while bits != 0 {
+                result.push(index.words[chunk * 64 + bits.trailing_zeros() as usize].clone());
+                bits &= bits - 1;
+            }
+            true
+        });
+        result
+    }
+
+    fn is_viable_with_min_score<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> bool {
+        let mut result = false;
+        self.visit_chunks(pattern, min_score, |_, _, _| {
+            result = true;
+            false
+        });
+        result
+    }
+
+    fn visit_matches<T: Iterator<Item = char> + Clone, F: FnMut(&str, Score) -> bool>(
+        &self,
+        pattern: T,
+        min_score: Score,
+        mut visit: F,
+    ) -> bool {
+        let mut complete = true;
+        self.visit_chunks(pattern, min_score, |index, chunk, mut bits| {
+            while bits != 0 {
+                let id = chunk * 64 + bits.trailing_zeros() as usize;
+                if !visit(&index.words[id], index.scores[id]) {
+                    complete = false;
+                    return false;
+                }
+                bits &= bits - 1;
+            }
+            true
+        });
+        complete
+    }
+
+    fn count_matches<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> usize {
+        let mut result = 0;
+        self.visit_chunks(pattern, min_score, |_, _, bits| {
+            result += bits.count_ones() as usize;
+            true
+        });
+        result
+    }
+
+    fn normalizer(&self) -> &Normalizer {
+        &self.normalizer
+    }
+
+    fn score(&self, word: &str) -> Option<Score> {
+        let index = self.lengths.get(word.chars().count())?;
+        let id = *index.ids.get(word)?;
+        Some(index.scores[id as usize])
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::index::WordIndex;
+
+    use super::BitsetIndex;
+
+    #[test]
+    fn bitset_index_works() {
+        let index = BitsetIndex::build_scored(vec![
+            (String::from("bass"), 60),
+            (String::from("bats"), 40),
+            (String::from("bess"), 20),
+            (String::from("be"), 50),
+        ]);
+
+        assert_eq!(
+            index.words_with_min_score("B SS".chars(), 0),
+            vec!["BASS", "BESS"]
+        );
+        assert_eq!(index.words_with_min_score("B SS".chars(), 30), vec!["BASS"]);
+        assert_eq!(index.count_matches("    ".chars(), 0), 3);
+        assert_eq!(index.count_matches("    ".chars(), 30), 2);
+        assert!(index.is_viable_with_min_score("BE".chars(), 50));
+        assert!(!index.is_viable_with_min_score("BE S".chars(), 30));
+        assert!(!index.is_viable_with_min_score("B?SS".chars(), 0));
+        assert!(!index.is_viable_with_min_score("     ".chars(), 0));
+        assert_eq!(
+            index.first_matches("    ".chars(), 0, 2),
+            vec!["BASS", "BATS"]
+        );
+        assert_eq!(
+            index.find_match("    ".chars(), 0, |_, score| score < 30),
+            Some(String::from("BESS"))
+        );
+        assert_eq!(index.score("BATS"), Some(40));
+        assert_eq!(index.score("BES"), None);
+    }
+
+    #[test]
+    fn many_words_work() {
+        let words: Vec<String> = (0..200)
+            .map(|id| {
+                format!(
+                    "{}{}",
+                    (b'A' + (id % 26) as u8) as char,
+                    (b'A' + (id / 26) as u8) as char
+                )
+            })
+            .collect();
+        let index = BitsetIndex::build(words);
+
+        assert_eq!(index.count_matches("  ".chars(), 0), 200);
+        assert_eq!(index.count_matches("A ".chars(), 0), 8);
+        assert_eq!(index.words_with_min_score(" H".chars(), 0).len(), 18);
+    }
+}
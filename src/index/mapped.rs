@@ -0,0 +1,707 @@
+/*!
+A word index that is queried directly from the bytes of a file, so that opening it
+costs a memory map rather than a deserialization.
+
+A file starts with a fixed size header, followed by these sections:
+
+1. The alphabet, one ASCII byte per letter.
+2. The arena index of the root for each word length, as `u32`s.
+3. The nodes of a `Trie`, breadth-first, as `(children: u64, first_child: u32,
+   score: u32)` records. A score of `u32::MAX` marks a node that ends no word.
+4. The offset of each word annotation in the next section, plus the end of the
+   section, as `u32`s.
+5. Word annotations, sorted by word: a tag byte, the word's length as a `u16`, the
+   word, and its display form if it has one.
+
+Every integer is little-endian. The header records the length of each section and
+an FNV-1a checksum of everything after it.
+
+To build an index from a word list, run `xwords wordlist build mylist.dict
+mylist.xwi`.
+*/
+
+use std::{convert::TryInto, fs::File, io::Write, ops::Deref, path::Path};
+
+use memmap2::Mmap;
+
+use crate::{
+    trie::{Score, Trie},
+    wordlist::{
+        normalize::{Alphabet, Normalizer},
+        tags::TagSet,
+    },
+};
+
+use super::{checksum, next_generation, WordIndex};
+
+/// The first bytes of every index file.
+pub const MAGIC: &[u8; 8] = b"XWORDIDX";
+
+/// The format version this crate reads and writes.
+pub const VERSION: u32 = 1;
+
+/// Magic, version, letter count, root count, node count, annotation count, annotation
+/// bytes, then checksum.
+const HEADER_LENGTH: usize = 8 + 4 * 6 + 8;
+
+const NODE_LENGTH: usize = 16;
+
+const NO_SCORE: u32 = u32::MAX;
+
+struct Header {
+    letters: usize,
+    roots: usize,
+    nodes: usize,
+    annotations: usize,
+    annotation_bytes: usize,
+    checksum: u64,
+}
+
+impl Header {
+    fn read(bytes: &[u8]) -> Result<Header, String> {
+        if bytes.len() < HEADER_LENGTH || &bytes[..8] != MAGIC {
+            return Err(String::from("Not an xwords index."));
+        }
+        let version = read_u32(bytes, 8);
+        if version != VERSION {
+            return Err(format!(
+                "Unsupported index version {}; expected {}.",
+                version, VERSION
+            ));
+        }
+        Ok(Header {
+            letters: read_u32(bytes, 12) as usize,
+            roots: read_u32(bytes, 16) as usize,
+            nodes: read_u32(bytes, 20) as usize,
+            annotations: read_u32(bytes, 24) as usize,
+            annotation_bytes: read_u32(bytes, 28) as usize,
+            checksum: u64::from_le_bytes(bytes[32..40].try_into().unwrap()),
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(MAGIC)?;
+        for value in [
+            VERSION,
+            self.letters as u32,
+            self.roots as u32,
+            self.nodes as u32,
+            self.annotations as u32,
+            self.annotation_bytes as u32,
+        ]
+        .iter()
+        {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.write_all(&self.checksum.to_le_bytes())
+    }
+
+    fn roots_offset(&self) -> usize {
+        HEADER_LENGTH + self.letters
+    }
+
+    fn nodes_offset(&self) -> usize {
+        self.roots_offset() + 4 * self.roots
+    }
+
+    fn annotation_offsets_offset(&self) -> usize {
+        self.nodes_offset() + NODE_LENGTH * self.nodes
+    }
+
+    fn annotations_offset(&self) -> usize {
+        self.annotation_offsets_offset() + 4 * (self.annotations + 1)
+    }
+
+    fn file_length(&self) -> usize {
+        self.annotations_offset() + self.annotation_bytes
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+/// Returns the word of an annotation record, or `None` if the record is too short to
+/// hold it.
+fn annotation_word(record: &[u8]) -> Option<&[u8]> {
+    let length = u16::from_le_bytes(record.get(1..3)?.try_into().unwrap()) as usize;
+    record.get(3..3 + length)
+}
+
+enum Bytes {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl Deref for Bytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Bytes::Mapped(map) => map,
+            Bytes::Owned(bytes) => bytes,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Node {
+    children: u64,
+    first_child: u32,
+    score: u32,
+}
+
+impl Node {
+    fn child(&self, letter: usize) -> Option<usize> {
+        let bit = 1 << letter;
+        if self.children & bit == 0 {
+            return None;
+        }
+        Some(self.first_child as usize + (self.children & (bit - 1)).count_ones() as usize)
+    }
+
+    fn score(&self) -> Option<Score> {
+        if self.score == NO_SCORE {
+            None
+        } else {
+            Some(self.score)
+        }
+    }
+}
+
+/// A `Trie` written with `MappedIndex::write` and read in place. Lookups decode only
+/// the nodes they visit, so an index opened with `open_lazy` is ready immediately and
+/// only pages in the parts of the file that are used. Such an index has not been
+/// checked against its checksum, so lookups that reach a damaged node or annotation
+/// match nothing there; `MappedIndex::words` reports damaged nodes as an error.
+pub struct MappedIndex {
+    bytes: Bytes,
+    header: Header,
+    normalizer: Normalizer,
+    /// The alphabet index of each ASCII character, or `u8::MAX`.
+    letter_indices: [u8; 128],
+    generation: u64,
+}
+
+impl MappedIndex {
+    /// Writes `trie` in the format `MappedIndex` reads.
+    pub fn write<W: Write>(trie: &Trie, mut writer: W) -> Result<(), String> {
+        let (root_count, packed) = trie.packed_nodes();
+        let mut body = vec![];
+        body.extend(
+            trie.normalizer()
+                .alphabet()
+                .letters()
+                .iter()
+                .map(|c| *c as u8),
+        );
+        for root in 0..root_count as u32 {
+            body.extend_from_slice(&root.to_le_bytes());
+        }
+        let mut first_child = root_count;
+        for (children, score) in packed.iter() {
+            let score = match score {
+                Some(NO_SCORE) => {
+                    return Err(format!("Scores must be less than {}.", NO_SCORE));
+                }
+                Some(score) => *score,
+                None => NO_SCORE,
+            };
+            body.extend_from_slice(&children.to_le_bytes());
+            body.extend_from_slice(&(first_child as u32).to_le_bytes());
+            body.extend_from_slice(&score.to_le_bytes());
+            first_child += children.count_ones() as usize;
+        }
+
+        let annotations = trie.annotations();
+        let mut records = vec![];
+        for (word, (tags, display)) in annotations.iter() {
+            body.extend_from_slice(&(records.len() as u32).to_le_bytes());
+            records.push(tags.bits());
+            records.extend_from_slice(&(word.len() as u16).to_le_bytes());
+            records.extend_from_slice(word.as_bytes());
+            records.extend_from_slice(display.unwrap_or_default().as_bytes());
+        }
+        body.extend_from_slice(&(records.len() as u32).to_le_bytes());
+        body.extend(records.iter());
+
+        let header = Header {
+            letters: trie.normalizer().alphabet().letters().len(),
+            roots: root_count,
+            nodes: packed.len(),
+            annotations: annotations.len(),
+            annotation_bytes: records.len(),
+            checksum: checksum(&body),
+        };
+        header
+            .write(&mut writer)
+            .and_then(|_| writer.write_all(&body))
+            .map_err(|err| format!("Failed to write index: {}", err))
+    }
+
+    /// Memory maps an index and checks it against its checksum, which reads the
+    /// whole file once.
+    pub fn open(path: &Path) -> Result<MappedIndex, String> {
+        let index = MappedIndex::open_lazy(path)?;
+        index
+            .verify()
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        Ok(index)
+    }
+
+    /// Memory maps an index, checking only its header. Nothing else is read until it
+    /// is needed, so opening takes the same time regardless of the size of the index;
+    /// use `verify` to detect corruption.
+    ///
+    /// As with any memory map, the file must not be modified while it is open.
+    pub fn open_lazy(path: &Path) -> Result<MappedIndex, String> {
+        let file = File::open(path)
+            .map_err(|err| format!("Failed to open {}: {}", path.display(), err))?;
+        // Safety: the map is read-only, and the caller must not modify the file while
+        // it is mapped.
+        let map = unsafe { Mmap::map(&file) }
+            .map_err(|err| format!("Failed to map {}: {}", path.display(), err))?;
+        MappedIndex::from_storage(Bytes::Mapped(map))
+            .map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    /// Reads an index from memory, checking it against its checksum.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<MappedIndex, String> {
+        let index = MappedIndex::from_storage(Bytes::Owned(bytes))?;
+        index.verify()?;
+        Ok(index)
+    }
+
+    fn from_storage(bytes: Bytes) -> Result<MappedIndex, String> {
+        let header = Header::read(&bytes)?;
+        if bytes.len() != header.file_length() {
+            return Err(format!(
+                "Index is {} bytes long; its header expects {}.",
+                bytes.len(),
+                header.file_length()
+            ));
+        }
+        if header.roots > header.nodes {
+            return Err(String::from("Index has more roots than nodes."));
+        }
+
+        let letters: String = bytes[HEADER_LENGTH..header.roots_offset()]
+            .iter()
+            .map(|byte| *byte as char)
+            .collect();
+        let alphabet = Alphabet::new(&letters)?;
+        let mut letter_indices = [u8::MAX; 128];
+        for (index, letter) in alphabet.letters().iter().enumerate() {
+            letter_indices[*letter as usize] = index as u8;
+        }
+
+        Ok(MappedIndex {
+            bytes,
+            header,
+            normalizer: Normalizer::new(alphabet),
+            letter_indices,
+            generation: next_generation(),
+        })
+    }
+
+    /// Checks the contents of the index against the checksum in its header.
+    pub fn verify(&self) -> Result<(), String> {
+        if checksum(&self.bytes[HEADER_LENGTH..]) != self.header.checksum {
+            return Err(String::from("Index does not match its checksum."));
+        }
+        Ok(())
+    }
+
+    pub fn normalizer(&self) -> &Normalizer {
+        &self.normalizer
+    }
+
+    /// Returns the form `word` should be shown in. See `Trie::display`.
+    pub fn display<'s>(&'s self, word: &'s str) -> &'s str {
+        match self.annotation(word) {
+            Some(record) if record.len() > 3 + word.len() => {
+                std::str::from_utf8(&record[3 + word.len()..]).unwrap_or(word)
+            }
+            _ => word,
+        }
+    }
+
+    /// Returns the words matching `pattern` that score at least `min_score`, or an
+    /// error if the lookup runs into a damaged part of the index. `WordIndex` lookups
+    /// on a damaged index match nothing there instead.
+    pub fn words<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> Result<Vec<String>, String> {
+        let mut result = vec![];
+        let mut partial = String::new();
+        if let Some(root) = self.root(pattern.clone().count()) {
+            self.collect_words(root, pattern, &mut partial, &mut result, min_score)?;
+        }
+        Ok(result)
+    }
+
+    fn root(&self, length: usize) -> Option<usize> {
+        if length >= self.header.roots {
+            return None;
+        }
+        Some(read_u32(&self.bytes, self.header.roots_offset() + 4 * length) as usize)
+    }
+
+    /// Decodes node `index`, checking that it and its children lie within the index,
+    /// since a lazily opened index has not been checked against its checksum.
+    fn node(&self, index: usize) -> Result<Node, String> {
+        if index >= self.header.nodes {
+            return Err(format!("Index has no node {}.", index));
+        }
+        let offset = self.header.nodes_offset() + NODE_LENGTH * index;
+        let bytes = &self.bytes[offset..offset + NODE_LENGTH];
+        let node = Node {
+            children: u64::from_le_bytes(bytes[..8].try_into().unwrap()),
+            first_child: u32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+            score: u32::from_le_bytes(bytes[12..].try_into().unwrap()),
+        };
+        let letters = self.header.letters;
+        if (letters < 64 && node.children >> letters != 0)
+            || node.first_child as usize + node.children.count_ones() as usize > self.header.nodes
+        {
+            return Err(format!("Node {} of the index is damaged.", index));
+        }
+        Ok(node)
+    }
+
+    fn letter(&self, letter: usize) -> char {
+        self.normalizer.alphabet().letters()[letter]
+    }
+
+    fn child(&self, node: usize, c: char) -> Result<Option<usize>, String> {
+        match self.letter_indices.get(c as usize) {
+            Some(letter) if *letter != u8::MAX => Ok(self.node(node)?.child(*letter as usize)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Finds the annotation record of `word` by binary search. Records whose offsets
+    /// or lengths do not fit the annotation section end the search with `None`.
+    fn annotation(&self, word: &str) -> Option<&[u8]> {
+        let offsets = self.header.annotation_offsets_offset();
+        let start = self.header.annotations_offset();
+        let record = |index: usize| {
+            let from = read_u32(&self.bytes, offsets + 4 * index) as usize;
+            let to = read_u32(&self.bytes, offsets + 4 * (index + 1)) as usize;
+            if from > to || to > self.header.annotation_bytes {
+                return None;
+            }
+            Some(&self.bytes[start + from..start + to])
+        };
+
+        let (mut low, mut high) = (0, self.header.annotations);
+        while low < high {
+            let middle = (low + high) / 2;
+            let record = record(middle)?;
+            match annotation_word(record)?.cmp(word.as_bytes()) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return Some(record),
+            }
+        }
+        None
+    }
+
+    fn collect_words<T: Iterator<Item = char> + Clone>(
+        &self,
+        node: usize,
+        mut pattern: T,
+        partial: &mut String,
+        result: &mut Vec<String>,
+        min_score: Score,
+    ) -> Result<(), String> {
+        let current = self.node(node)?;
+        match pattern.next() {
+            Some(' ') => {
+                let mut remaining = current.children;
+                let mut child = current.first_child as usize;
+                while remaining != 0 {
+                    partial.push(self.letter(remaining.trailing_zeros() as usize));
+                    self.collect_words(child, pattern.clone(), partial, result, min_score)?;
+                    partial.pop();
+                    remaining &= remaining - 1;
+                    child += 1;
+                }
+            }
+            Some(c) => {
+                if let Some(child) = self.child(node, c)? {
+                    partial.push(c);
+                    self.collect_words(child, pattern, partial, result, min_score)?;
+                    partial.pop();
+                }
+            }
+            None => {
+                if current.score().is_some_and(|score| score >= min_score) {
+                    result.push(partial.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_from<T: Iterator<Item = char> + Clone, F: FnMut(&str, Score) -> bool>(
+        &self,
+        node: usize,
+        mut pattern: T,
+        partial: &mut String,
+        min_score: Score,
+        visit: &mut F,
+    ) -> Result<bool, String> {
+        let current = self.node(node)?;
+        match pattern.next() {
+            Some(' ') => {
+                let mut remaining = current.children;
+                let mut child = current.first_child as usize;
+                while remaining != 0 {
+                    partial.push(self.letter(remaining.trailing_zeros() as usize));
+                    let go_on =
+                        self.visit_from(child, pattern.clone(), partial, min_score, visit)?;
+                    partial.pop();
+                    if !go_on {
+                        return Ok(false);
+                    }
+                    remaining &= remaining - 1;
+                    child += 1;
+                }
+                Ok(true)
+            }
+            Some(c) => match self.child(node, c)? {
+                Some(child) => {
+                    partial.push(c);
+                    let go_on = self.visit_from(child, pattern, partial, min_score, visit)?;
+                    partial.pop();
+                    Ok(go_on)
+                }
+                None => Ok(true),
+            },
+            None => match current.score() {
+                Some(score) if score >= min_score => Ok(visit(partial, score)),
+                _ => Ok(true),
+            },
+        }
+    }
+
+    fn is_viable_from<T: Iterator<Item = char> + Clone>(
+        &self,
+        node: usize,
+        mut chars: T,
+        min_score: Score,
+    ) -> Result<bool, String> {
+        let current = self.node(node)?;
+        match chars.next() {
+            None => Ok(current.score().is_some_and(|score| score >= min_score)),
+            Some(' ') => {
+                for offset in 0..current.children.count_ones() as usize {
+                    let child = current.first_child as usize + offset;
+                    if self.is_viable_from(child, chars.clone(), min_score)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Some(c) => match self.child(node, c)? {
+                None => Ok(false),
+                Some(child) => self.is_viable_from(child, chars, min_score),
+            },
+        }
+    }
+}
+
+impl WordIndex for MappedIndex {
+    fn words_with_min_score<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> Vec<String> {
+        self.words(pattern, min_score).unwrap_or_default()
+    }
+
+    fn is_viable_with_min_score<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> bool {
+        match self.root(pattern.clone().count()) {
+            Some(root) => self
+                .is_viable_from(root, pattern, min_score)
+                .unwrap_or(false),
+            None => false,
+        }
+    }
+
+    fn visit_matches<T: Iterator<Item = char> + Clone, F: FnMut(&str, Score) -> bool>(
+        &self,
+        pattern: T,
+        min_score: Score,
+        mut visit: F,
+    ) -> bool {
+        let mut partial = String::new();
+        match self.root(pattern.clone().count()) {
+            Some(root) => self
+                .visit_from(root, pattern, &mut partial, min_score, &mut visit)
+                .unwrap_or(true),
+            None => true,
+        }
+    }
+
+    fn normalizer(&self) -> &Normalizer {
+        MappedIndex::normalizer(self)
+    }
+
+    fn score(&self, word: &str) -> Option<Score> {
+        let mut node = self.root(word.chars().count())?;
+        for c in word.chars() {
+            node = self.child(node, c).ok()??;
+        }
+        self.node(node).ok()?.score()
+    }
+
+    fn display<'s>(&'s self, word: &'s str) -> &'s str {
+        MappedIndex::display(self, word)
+    }
+
+    fn tags(&self, word: &str) -> TagSet {
+        self.annotation(word)
+            .map(|record| TagSet::from_bits(record[0]))
+            .unwrap_or_default()
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        index::WordIndex,
+        trie::Trie,
+        wordlist::{
+            tags::{Tag, TagSet},
+            WordListFormat,
+        },
+    };
+
+    use super::{Bytes, Header, MappedIndex, NODE_LENGTH};
+
+    fn write(trie: &Trie) -> Vec<u8> {
+        let mut bytes = vec![];
+        MappedIndex::write(trie, &mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn mapped_index_works() {
+        let mut trie = Trie::from_reader(
+            "BASS;60\nBATS;40\nBESS;20\nBE;50\nBilly Joel;50;proper\nATA;10;partial\n".as_bytes(),
+            WordListFormat::Dict,
+        )
+        .unwrap();
+        trie.insert("ASSET", 30);
+        trie.remove("BE");
+        let index = MappedIndex::from_bytes(write(&trie)).unwrap();
+
+        for pattern in ["B SS", "    ", "  ", "     ", "BILLYJOEL", "?ATS"].iter() {
+            assert_eq!(
+                index.words_with_min_score(pattern.chars(), 0),
+                trie.words(pattern.chars())
+            );
+        }
+        assert_eq!(index.words_with_min_score("B SS".chars(), 30), vec!["BASS"]);
+        assert_eq!(index.count_matches("B SS".chars(), 0), 2);
+        assert_eq!(
+            index.first_matches("    ".chars(), 0, 2),
+            vec!["BASS", "BATS"]
+        );
+        assert!(index.is_viable_with_min_score("BE S".chars(), 20));
+        assert!(!index.is_viable_with_min_score("BE S".chars(), 30));
+        assert!(!index.is_viable_with_min_score("  ".chars(), 0));
+        assert_eq!(index.score("ASSET"), Some(30));
+        assert_eq!(index.score("BE"), None);
+        assert_eq!(index.tags("ATA"), TagSet::default().with(Tag::Partial));
+        assert!(index.tags("BASS").is_empty());
+        assert_eq!(index.display("BILLYJOEL"), "Billy Joel");
+        assert_eq!(index.display("BASS"), "BASS");
+    }
+
+    #[test]
+    fn corruption_is_detected() {
+        let trie = Trie::build(vec![String::from("BASS"), String::from("BATS")]);
+        let bytes = write(&trie);
+
+        let mut corrupt = bytes.clone();
+        *corrupt.last_mut().unwrap() ^= 1;
+        assert!(MappedIndex::from_bytes(corrupt).is_err());
+
+        let mut future = bytes.clone();
+        future[8] = 2;
+        let err = MappedIndex::from_bytes(future).err().unwrap();
+        assert!(err.contains("version 2"), "{}", err);
+
+        assert!(MappedIndex::from_bytes(bytes[..bytes.len() - 1].to_vec()).is_err());
+        assert!(MappedIndex::from_bytes(b"not an index".to_vec()).is_err());
+        assert!(MappedIndex::from_bytes(bytes.clone()).is_ok());
+
+        // Lazily opened indexes are not checked against their checksum, so lookups
+        // check the nodes they visit.
+        let header = Header::read(&bytes).unwrap();
+        let mut dangling = bytes;
+        for node in 0..header.nodes {
+            let offset = header.nodes_offset() + NODE_LENGTH * node + 8;
+            dangling[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        }
+        let index = MappedIndex::from_storage(Bytes::Owned(dangling)).unwrap();
+        assert!(index.words("B   ".chars(), 0).is_err());
+        assert!(index.words_with_min_score("B   ".chars(), 0).is_empty());
+        assert!(!index.is_viable_with_min_score("BASS".chars(), 0));
+        assert_eq!(index.score("BASS"), None);
+
+        let trie = Trie::build_tagged(vec![
+            (
+                String::from("BASS"),
+                50,
+                TagSet::default().with(Tag::ProperNoun),
+            ),
+            (
+                String::from("BATS"),
+                50,
+                TagSet::default().with(Tag::Partial),
+            ),
+        ]);
+        let bytes = write(&trie);
+        let header = Header::read(&bytes).unwrap();
+        let offsets = header.annotation_offsets_offset();
+        // Damaged annotation offsets leave a record too short for its word, or past
+        // the end of the section.
+        for offset in [1, u32::MAX].iter() {
+            let mut damaged = bytes.clone();
+            damaged[offsets + 4..offsets + 8].copy_from_slice(&offset.to_le_bytes());
+            let index = MappedIndex::from_storage(Bytes::Owned(damaged)).unwrap();
+            for word in ["BASS", "BATS"].iter() {
+                assert!(index.tags(word).is_empty());
+                assert_eq!(index.display(word), *word);
+            }
+        }
+    }
+
+    #[test]
+    fn open_works() {
+        let trie = Trie::build(vec![String::from("BASS"), String::from("BATS")]);
+        let path = std::env::temp_dir().join(format!("xwords-{}.xwi", std::process::id()));
+        std::fs::write(&path, write(&trie)).unwrap();
+
+        let index = MappedIndex::open_lazy(&path).unwrap();
+        assert_eq!(index.words_with_min_score("BAS ".chars(), 0), vec!["BASS"]);
+        assert!(MappedIndex::open(&path).is_ok());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(MappedIndex::open(&path).is_err());
+    }
+}
@@ -0,0 +1,282 @@
+/*!
+Word indexes that answer partial word lookups. `Trie` is the default; other indexes
+implement `WordIndex` so that `Filler` can be used with any of them.
+*/
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{
+    search::LetterOptions,
+    trie::{Score, Trie},
+    wordlist::{normalize::Normalizer, tags::TagSet},
+};
+
+pub mod bitset;
+pub mod mapped;
+pub mod serialized;
+
+/// Lookups of partially filled words. In a pattern, ` ` matches any letter and any
+/// other character matches itself.
+pub trait WordIndex {
+    /// Returns every word matching `pattern` that scores at least `min_score`.
+    fn words_with_min_score<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> Vec<String>;
+
+    /// Returns whether any word matching `pattern` scores at least `min_score`.
+    fn is_viable_with_min_score<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> bool;
+
+    /// Calls `visit` with each word matching `pattern` that scores at least
+    /// `min_score`, and its score, without collecting them. Stops as soon as `visit`
+    /// returns false, and returns whether every match was visited.
+    ///
+    /// Words are visited in the order `words_with_min_score` returns them. The word
+    /// passed to `visit` is only borrowed; indexes may reuse its buffer.
+    fn visit_matches<T: Iterator<Item = char> + Clone, F: FnMut(&str, Score) -> bool>(
+        &self,
+        pattern: T,
+        min_score: Score,
+        mut visit: F,
+    ) -> bool {
+        self.words_with_min_score(pattern, min_score)
+            .iter()
+            .all(|word| visit(word, self.score(word).unwrap_or_default()))
+    }
+
+    /// Counts the words `words_with_min_score` would return without collecting them.
+    fn count_matches<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> usize {
+        let mut count = 0;
+        self.visit_matches(pattern, min_score, |_, _| {
+            count += 1;
+            true
+        });
+        count
+    }
+
+    /// Returns the first `limit` words `words_with_min_score` would return.
+    fn first_matches<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+        limit: usize,
+    ) -> Vec<String> {
+        let mut result = Vec::with_capacity(limit.min(16));
+        if limit > 0 {
+            self.visit_matches(pattern, min_score, |word, _| {
+                result.push(String::from(word));
+                result.len() < limit
+            });
+        }
+        result
+    }
+
+    /// Returns the first match for which `predicate` holds.
+    fn find_match<T: Iterator<Item = char> + Clone, F: FnMut(&str, Score) -> bool>(
+        &self,
+        pattern: T,
+        min_score: Score,
+        mut predicate: F,
+    ) -> Option<String> {
+        let mut result = None;
+        self.visit_matches(pattern, min_score, |word, score| {
+            if predicate(word, score) {
+                result = Some(String::from(word));
+            }
+            result.is_none()
+        });
+        result
+    }
+
+    /// Returns the letters that the words matching `pattern` and scoring at least
+    /// `min_score` put at each position of it.
+    fn letter_options<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> LetterOptions {
+        self.collect_letter_options(pattern, min_score, false)
+    }
+
+    /// Like `letter_options`, but also counts the matching words with each letter at
+    /// each position.
+    fn letter_counts<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> LetterOptions {
+        self.collect_letter_options(pattern, min_score, true)
+    }
+
+    /// Builds `LetterOptions` by visiting every match. Indexes override this when
+    /// they can do better.
+    fn collect_letter_options<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+        with_counts: bool,
+    ) -> LetterOptions {
+        let normalizer = self.normalizer();
+        let mut result = LetterOptions::new(normalizer, pattern.clone().count(), with_counts);
+        self.visit_matches(pattern, min_score, |word, _| {
+            for (position, c) in word.chars().enumerate() {
+                if let Some(letter) = normalizer.alphabet().index_of(c) {
+                    result.add(position, letter, 1);
+                }
+            }
+            true
+        });
+        result
+    }
+
+    /// Like `letter_options`, but each position is constrained by a bitmask over the
+    /// alphabet: only words whose letters are all within the masks of their positions
+    /// match. Used to keep fills arc consistent.
+    fn masked_letter_options(&self, masks: &[u64], min_score: Score) -> LetterOptions {
+        let normalizer = self.normalizer();
+        let letters = normalizer.alphabet().letters();
+        // Look up a pattern with the squares that allow a single letter filled in, then
+        // check the rest of the masks word by word.
+        let pattern = masks.iter().map(|mask| {
+            if mask.count_ones() == 1 {
+                letters[mask.trailing_zeros() as usize]
+            } else {
+                ' '
+            }
+        });
+        let mut result = LetterOptions::new(normalizer, masks.len(), false);
+        let mut indices = Vec::with_capacity(masks.len());
+        self.visit_matches(pattern, min_score, |word, _| {
+            indices.clear();
+            indices.extend(
+                word.chars()
+                    .filter_map(|c| normalizer.alphabet().index_of(c)),
+            );
+            if indices.len() == masks.len()
+                && indices
+                    .iter()
+                    .zip(masks.iter())
+                    .all(|(letter, mask)| mask & (1 << letter) != 0)
+            {
+                for (position, letter) in indices.iter().enumerate() {
+                    result.add(position, *letter, 1);
+                }
+            }
+            true
+        });
+        result
+    }
+
+    /// The normalizer patterns are expected to have been normalized with.
+    fn normalizer(&self) -> &Normalizer;
+
+    /// Returns the score of `word`, or `None` if it is not in the index.
+    fn score(&self, word: &str) -> Option<Score>;
+
+    /// Returns the form `word` should be shown in, e.g. `Billie Jean King` rather
+    /// than `BILLIEJEANKING`. Indexes that do not track display forms return `word`.
+    fn display<'s>(&'s self, word: &'s str) -> &'s str {
+        word
+    }
+
+    /// Returns the tags of `word`. Indexes that do not track tags report none.
+    fn tags(&self, _word: &str) -> TagSet {
+        TagSet::default()
+    }
+
+    /// Identifies the contents of the index, so that cached lookups can tell when
+    /// they are stale. It changes whenever words are added or removed, and indexes
+    /// built separately never share one.
+    fn generation(&self) -> u64;
+}
+
+/// 64-bit FNV-1a, used to detect corrupt index files.
+pub(crate) fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+/// Returns a generation no index has used yet.
+pub(crate) fn next_generation() -> u64 {
+    NEXT_GENERATION.fetch_add(1, Ordering::Relaxed)
+}
+
+impl WordIndex for Trie {
+    fn words_with_min_score<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> Vec<String> {
+        Trie::words_with_min_score(self, pattern, min_score)
+    }
+
+    fn is_viable_with_min_score<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> bool {
+        Trie::is_viable_with_min_score(self, pattern, min_score)
+    }
+
+    fn visit_matches<T: Iterator<Item = char> + Clone, F: FnMut(&str, Score) -> bool>(
+        &self,
+        pattern: T,
+        min_score: Score,
+        visit: F,
+    ) -> bool {
+        Trie::visit_matches(self, pattern, min_score, visit)
+    }
+
+    fn count_matches<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> usize {
+        Trie::count_matches(self, pattern, min_score)
+    }
+
+    fn collect_letter_options<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+        with_counts: bool,
+    ) -> LetterOptions {
+        Trie::collect_letter_options(self, pattern, min_score, with_counts)
+    }
+
+    fn masked_letter_options(&self, masks: &[u64], min_score: Score) -> LetterOptions {
+        Trie::masked_letter_options(self, masks, min_score)
+    }
+
+    fn normalizer(&self) -> &Normalizer {
+        Trie::normalizer(self)
+    }
+
+    fn score(&self, word: &str) -> Option<Score> {
+        Trie::score(self, word)
+    }
+
+    fn display<'s>(&'s self, word: &'s str) -> &'s str {
+        Trie::display(self, word)
+    }
+
+    fn tags(&self, word: &str) -> TagSet {
+        Trie::tags(self, word)
+    }
+
+    fn generation(&self) -> u64 {
+        Trie::generation(self)
+    }
+}
@@ -0,0 +1,301 @@
+/*!
+The `.bincode` format for saving a `Trie`, with a header that lets stale or damaged
+files be rejected with a specific error instead of a generic decoding failure.
+
+A file starts with:
+
+1. The magic bytes `XWORDTRI`.
+2. The format version, as a `u32`.
+3. The length of the alphabet as a `u32`, then the alphabet, one ASCII byte per
+   letter.
+4. The number of words, as a `u64`.
+5. The length of the body, as a `u64`.
+6. An FNV-1a checksum of the body, as a `u64`.
+
+The body, the `Trie` encoded with bincode, follows. Every integer is little-endian.
+*/
+
+use std::{
+    convert::TryInto,
+    fmt,
+    fs::File,
+    io::{BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::trie::Trie;
+
+use super::checksum;
+
+/// The first bytes of every serialized trie.
+pub const MAGIC: &[u8; 8] = b"XWORDTRI";
+
+/// The format version this crate reads and writes. It changes whenever the layout
+/// of the header or of `Trie` does.
+pub const VERSION: u32 = 1;
+
+/// Why a serialized trie could not be read or written.
+#[derive(Debug)]
+pub enum TrieFormatError {
+    Io(std::io::Error),
+    /// The file does not start with `MAGIC`. Files written before the format had a
+    /// header fail this way.
+    BadMagic,
+    UnsupportedVersion {
+        found: u32,
+        supported: u32,
+    },
+    /// The file ends before the header or body does.
+    Truncated,
+    ChecksumMismatch {
+        expected: u64,
+        found: u64,
+    },
+    /// The body matches its checksum but does not decode, or its alphabet is
+    /// invalid.
+    Corrupt(String),
+    /// The decoded trie uses a different alphabet than the header records.
+    AlphabetMismatch {
+        header: String,
+        trie: String,
+    },
+    /// The decoded trie holds a different number of words than the header records.
+    WordCountMismatch {
+        header: u64,
+        trie: u64,
+    },
+}
+
+impl fmt::Display for TrieFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrieFormatError::Io(err) => write!(f, "{}", err),
+            TrieFormatError::BadMagic => write!(
+                f,
+                "Not a serialized trie; it may predate the current format. \
+                 Rebuild it with `xwords wordlist build`."
+            ),
+            TrieFormatError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "Unsupported trie version {}; expected {}. Rebuild it with \
+                 `xwords wordlist build`.",
+                found, supported
+            ),
+            TrieFormatError::Truncated => write!(f, "The trie is truncated."),
+            TrieFormatError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "The trie does not match its checksum: expected {:016x}, found {:016x}.",
+                expected, found
+            ),
+            TrieFormatError::Corrupt(err) => write!(f, "The trie is corrupt: {}", err),
+            TrieFormatError::AlphabetMismatch { header, trie } => write!(
+                f,
+                "The trie's alphabet {:?} does not match its header's {:?}.",
+                trie, header
+            ),
+            TrieFormatError::WordCountMismatch { header, trie } => write!(
+                f,
+                "The trie holds {} words, but its header records {}.",
+                trie, header
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TrieFormatError {}
+
+impl From<std::io::Error> for TrieFormatError {
+    fn from(err: std::io::Error) -> TrieFormatError {
+        TrieFormatError::Io(err)
+    }
+}
+
+impl From<TrieFormatError> for String {
+    fn from(err: TrieFormatError) -> String {
+        err.to_string()
+    }
+}
+
+fn alphabet(trie: &Trie) -> String {
+    trie.normalizer().alphabet().letters().iter().collect()
+}
+
+/// Writes `trie` with a header.
+pub fn write<W: Write>(trie: &Trie, mut writer: W) -> Result<(), TrieFormatError> {
+    let body = bincode::serialize(trie).map_err(|err| TrieFormatError::Corrupt(err.to_string()))?;
+    let alphabet = alphabet(trie);
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&(alphabet.len() as u32).to_le_bytes())?;
+    writer.write_all(alphabet.as_bytes())?;
+    writer.write_all(&(trie.word_count() as u64).to_le_bytes())?;
+    writer.write_all(&(body.len() as u64).to_le_bytes())?;
+    writer.write_all(&checksum(&body).to_le_bytes())?;
+    writer.write_all(&body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a trie written by `write`, checking its header, checksum, alphabet and word
+/// count.
+pub fn read<R: Read>(mut reader: R) -> Result<Trie, TrieFormatError> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    from_bytes(&bytes)
+}
+
+/// Like `read`, for a trie that is already in memory.
+pub fn from_bytes(bytes: &[u8]) -> Result<Trie, TrieFormatError> {
+    let mut cursor = Cursor { bytes, offset: 0 };
+    if cursor
+        .take(MAGIC.len())
+        .map_err(|_| TrieFormatError::BadMagic)?
+        != MAGIC
+    {
+        return Err(TrieFormatError::BadMagic);
+    }
+    let version = cursor.u32()?;
+    if version != VERSION {
+        return Err(TrieFormatError::UnsupportedVersion {
+            found: version,
+            supported: VERSION,
+        });
+    }
+    let letters = cursor.u32()? as usize;
+    let header_alphabet = String::from_utf8(cursor.take(letters)?.to_vec())
+        .map_err(|_| TrieFormatError::Corrupt(String::from("the alphabet is not ASCII")))?;
+    let word_count = cursor.u64()?;
+    let body_length = cursor.u64()? as usize;
+    let expected = cursor.u64()?;
+    let body = cursor.take(body_length)?;
+
+    let found = checksum(body);
+    if found != expected {
+        return Err(TrieFormatError::ChecksumMismatch { expected, found });
+    }
+    let trie: Trie =
+        bincode::deserialize(body).map_err(|err| TrieFormatError::Corrupt(err.to_string()))?;
+
+    let trie_alphabet = alphabet(&trie);
+    if trie_alphabet != header_alphabet {
+        return Err(TrieFormatError::AlphabetMismatch {
+            header: header_alphabet,
+            trie: trie_alphabet,
+        });
+    }
+    let trie_count = trie.word_count() as u64;
+    if trie_count != word_count {
+        return Err(TrieFormatError::WordCountMismatch {
+            header: word_count,
+            trie: trie_count,
+        });
+    }
+    Ok(trie)
+}
+
+/// Writes `trie` to `path` with a header.
+pub fn save(trie: &Trie, path: &Path) -> Result<(), String> {
+    let file = File::create(path)
+        .map_err(|err| format!("Failed to create {}: {}", path.display(), err))?;
+    write(trie, BufWriter::new(file))
+        .map_err(|err| format!("Failed to write {}: {}", path.display(), err))
+}
+
+struct Cursor<'s> {
+    bytes: &'s [u8],
+    offset: usize,
+}
+
+impl<'s> Cursor<'s> {
+    fn take(&mut self, length: usize) -> Result<&'s [u8], TrieFormatError> {
+        let end = self
+            .offset
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(TrieFormatError::Truncated)?;
+        let result = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(result)
+    }
+
+    fn u32(&mut self) -> Result<u32, TrieFormatError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, TrieFormatError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::trie::Trie;
+
+    use super::{from_bytes, write, TrieFormatError, VERSION};
+
+    fn serialize(trie: &Trie) -> Vec<u8> {
+        let mut bytes = vec![];
+        write(trie, &mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn round_trip_works() {
+        let mut trie = Trie::build(vec![String::from("BAT"), String::from("ASSET")]);
+        trie.insert("BASS", 70);
+
+        let copy = from_bytes(&serialize(&trie)).unwrap();
+
+        assert_eq!(copy.words("   ".chars()), vec!["BAT"]);
+        assert_eq!(copy.score("BASS"), Some(70));
+        assert_eq!(copy.word_count(), 3);
+    }
+
+    #[test]
+    fn errors_are_specific() {
+        let trie = Trie::build(vec![String::from("BAT"), String::from("ASSET")]);
+        let bytes = serialize(&trie);
+
+        let legacy = bincode::serialize(&trie).unwrap();
+        assert!(matches!(
+            from_bytes(&legacy),
+            Err(TrieFormatError::BadMagic)
+        ));
+        assert!(matches!(from_bytes(b"XW"), Err(TrieFormatError::BadMagic)));
+
+        let mut newer = bytes.clone();
+        newer[8..12].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            from_bytes(&newer),
+            Err(TrieFormatError::UnsupportedVersion { found, .. }) if found == VERSION + 1
+        ));
+
+        assert!(matches!(
+            from_bytes(&bytes[..bytes.len() - 1]),
+            Err(TrieFormatError::Truncated)
+        ));
+
+        let mut corrupt = bytes.clone();
+        *corrupt.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            from_bytes(&corrupt),
+            Err(TrieFormatError::ChecksumMismatch { .. })
+        ));
+
+        // Header fields follow the magic, version, alphabet length and 26 letters.
+        let mut alphabet = bytes.clone();
+        alphabet[16] = b'a';
+        assert!(matches!(
+            from_bytes(&alphabet),
+            Err(TrieFormatError::AlphabetMismatch { .. })
+        ));
+
+        let mut count = bytes;
+        count[42..50].copy_from_slice(&5u64.to_le_bytes());
+        assert!(matches!(
+            from_bytes(&count),
+            Err(TrieFormatError::WordCountMismatch { header: 5, trie: 2 })
+        ));
+    }
+}
@@ -17,12 +17,19 @@ use crate::crossword::Crossword;
 use crate::crossword::Direction;
 use std::fs::File;
 
+pub mod bigrams;
+pub mod clues;
 pub mod crossword;
 pub mod fill;
+pub mod formats;
+pub mod index;
 pub mod parse;
+pub mod search;
 pub mod trie;
+pub mod wordlist;
 
+/// Fills a crossword with the word list returned by `Trie::load_default`.
 pub fn fill_crossword_with_default_wordlist(crossword: &Crossword) -> Result<Crossword, String> {
-    let trie = Trie::load_default().expect("Failed to load trie");
+    let trie = Trie::load_default()?;
     Filler::new(&trie).fill(crossword)
 }
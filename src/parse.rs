@@ -23,14 +23,14 @@ pub fn parse_word_boundaries(crossword: &Crossword) -> Vec<WordBoundary> {
             let current_char = byte_array[row * crossword.width + col] as char;
             if current_char != '*' {
                 // found a char; is it our first?
-                if start_row == None {
+                if start_row.is_none() {
                     start_row = Some(row);
                     start_col = Some(col);
                 }
                 length += 1;
             } else {
                 // If we don't have any data yet, just keep going
-                if start_row == None {
+                if start_row.is_none() {
                     continue;
                 }
                 let new_word = WordBoundary {
@@ -69,13 +69,13 @@ pub fn parse_word_boundaries(crossword: &Crossword) -> Vec<WordBoundary> {
             let current_char = byte_array[row * crossword.width + col] as char;
             if current_char != '*' {
                 // found a char; is it our first?
-                if start_row == None {
+                if start_row.is_none() {
                     start_row = Some(row);
                     start_col = Some(col);
                 }
                 length += 1;
             } else {
-                if start_row == None {
+                if start_row.is_none() {
                     continue;
                 }
                 let new_word = WordBoundary {
@@ -108,6 +108,40 @@ pub fn parse_word_boundaries(crossword: &Crossword) -> Vec<WordBoundary> {
     result
 }
 
+/// Assigns clue numbers to the word boundaries of a `Crossword`, following the usual
+/// convention: squares that start an entry are numbered from 1 in reading order, and an
+/// across and a down entry that start on the same square share a number.
+///
+/// Runs of a single square are not entries and are left unnumbered.
+pub fn number_word_boundaries(crossword: &Crossword) -> Vec<(usize, WordBoundary)> {
+    let mut word_boundaries: Vec<WordBoundary> = parse_word_boundaries(crossword)
+        .into_iter()
+        .filter(|word_boundary| word_boundary.length > 1)
+        .collect();
+    word_boundaries.sort_by_key(|word_boundary| {
+        (
+            word_boundary.start_row,
+            word_boundary.start_col,
+            word_boundary.direction == Direction::Down,
+        )
+    });
+
+    let mut result: Vec<(usize, WordBoundary)> = Vec::with_capacity(word_boundaries.len());
+    let mut number = 0;
+    for word_boundary in word_boundaries {
+        let shares_start = result.last().is_some_and(|(_, previous)| {
+            previous.start_row == word_boundary.start_row
+                && previous.start_col == word_boundary.start_col
+        });
+        if !shares_start {
+            number += 1;
+        }
+        result.push((number, word_boundary));
+    }
+
+    result
+}
+
 /// A representation of a word in a `Crossword`. Note that a `WordBoundary` is not
 /// attached to a specific `Crossword`, and that it is mostly used to represent
 /// a location in a grid.
@@ -141,7 +175,7 @@ impl WordBoundary {
 #[cfg(test)]
 mod tests {
 
-    use crate::parse::parse_word_boundaries;
+    use crate::parse::{number_word_boundaries, parse_word_boundaries};
 
     use crate::{Crossword, Direction};
 
@@ -264,4 +298,21 @@ ghi
             }
         );
     }
+
+    #[test]
+    fn number_word_boundaries_works() {
+        let c = Crossword::square(String::from("   \n * \n   ")).unwrap();
+
+        let result = number_word_boundaries(&c);
+
+        assert_eq!(
+            result,
+            vec![
+                (1, WordBoundary::new(0, 0, 3, Direction::Across)),
+                (1, WordBoundary::new(0, 0, 3, Direction::Down)),
+                (2, WordBoundary::new(0, 2, 3, Direction::Down)),
+                (3, WordBoundary::new(2, 0, 3, Direction::Across)),
+            ]
+        );
+    }
 }
@@ -0,0 +1,510 @@
+/*!
+A pattern language for looking up words, e.g. `B?SS`, `[AEIOU]??E` or `*ING`.
+
+| Syntax      | Matches                                    |
+|-------------|--------------------------------------------|
+| `A`         | the letter `A`                             |
+| `?` or ` `  | any one letter                             |
+| `[AEIOU]`   | any one of the listed letters              |
+| `[^AEIOU]`  | any one letter that is not listed          |
+| `*`         | any run of letters, including an empty one |
+
+Letters are normalized like word list entries, so `b?ss` and `B?SS` are equivalent.
+
+Words can also be looked up by the letters they use: see `anagrams` and
+`letter_bank`. `LetterOptions` summarizes the letters that the words matching a
+partially filled slot can put in each of its squares.
+*/
+
+use crate::{
+    trie::{Score, Trie},
+    wordlist::normalize::Normalizer,
+};
+
+/// One element of a `Pattern`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Element {
+    Letter(char),
+    /// Any one letter.
+    Any,
+    /// One letter from `letters`, or one letter not in `letters` if `negated`.
+    Class {
+        letters: Vec<char>,
+        negated: bool,
+    },
+    /// Any run of letters, including an empty one.
+    AnyRun,
+}
+
+impl Element {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Element::Letter(letter) => *letter == c,
+            Element::Any => true,
+            Element::Class { letters, negated } => letters.contains(&c) != *negated,
+            Element::AnyRun => true,
+        }
+    }
+}
+
+/// The elements of a pattern that a partial match may have reached, as a bitset.
+/// Bit `elements.len()` means the whole pattern has been matched.
+pub(crate) type States = u64;
+
+/// A parsed search pattern. Matching runs the pattern as a non-deterministic
+/// automaton, so a word is matched once no matter how many ways its `*`s can be
+/// expanded.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Pattern {
+    elements: Vec<Element>,
+}
+
+impl Pattern {
+    /// The maximum number of elements in a pattern.
+    pub const MAX_ELEMENTS: usize = 63;
+
+    /// Parses `text`, normalizing letters with `normalizer`.
+    pub fn parse(text: &str, normalizer: &Normalizer) -> Result<Pattern, String> {
+        let letter = |c: char| -> Result<char, String> {
+            let normalized = normalizer.normalize_square(c)?;
+            if normalizer.alphabet().contains(normalized) {
+                Ok(normalized)
+            } else {
+                Err(format!("{:?} is not a letter or wildcard.", c))
+            }
+        };
+
+        let mut elements = vec![];
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            elements.push(match c {
+                '?' | ' ' => Element::Any,
+                '*' => Element::AnyRun,
+                '[' => {
+                    let mut letters = vec![];
+                    let mut negated = false;
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        match c {
+                            ']' => {
+                                closed = true;
+                                break;
+                            }
+                            '^' if letters.is_empty() && !negated => negated = true,
+                            c => letters.push(letter(c)?),
+                        }
+                    }
+                    if !closed {
+                        return Err(format!("Unclosed character class in {:?}.", text));
+                    }
+                    if letters.is_empty() {
+                        return Err(format!("Empty character class in {:?}.", text));
+                    }
+                    Element::Class { letters, negated }
+                }
+                c => Element::Letter(letter(c)?),
+            });
+        }
+
+        if elements.is_empty() {
+            return Err(String::from("Patterns cannot be empty."));
+        }
+        if elements.len() > Pattern::MAX_ELEMENTS {
+            return Err(format!(
+                "Patterns can have at most {} elements.",
+                Pattern::MAX_ELEMENTS
+            ));
+        }
+        Ok(Pattern { elements })
+    }
+
+    pub fn elements(&self) -> &[Element] {
+        &self.elements
+    }
+
+    /// The length of the shortest word this pattern can match.
+    pub fn min_length(&self) -> usize {
+        self.elements
+            .iter()
+            .filter(|element| **element != Element::AnyRun)
+            .count()
+    }
+
+    /// The length of the longest word this pattern can match, or `None` if it
+    /// contains a `*`.
+    pub fn max_length(&self) -> Option<usize> {
+        if self.elements.contains(&Element::AnyRun) {
+            None
+        } else {
+            Some(self.elements.len())
+        }
+    }
+
+    /// Returns whether `word` matches the whole pattern.
+    pub fn matches(&self, word: &str) -> bool {
+        let mut states = self.start();
+        for c in word.chars() {
+            states = self.step(states, c);
+        }
+        self.accepts(states)
+    }
+
+    pub(crate) fn start(&self) -> States {
+        self.closure(1)
+    }
+
+    /// The states reached from `states` by matching `c`. Empty if no match is
+    /// possible.
+    pub(crate) fn step(&self, states: States, c: char) -> States {
+        let mut result = 0;
+        for (index, element) in self.elements.iter().enumerate() {
+            if states & (1 << index) == 0 {
+                continue;
+            }
+            if *element == Element::AnyRun {
+                result |= 1 << index;
+            } else if element.matches(c) {
+                result |= 1 << (index + 1);
+            }
+        }
+        self.closure(result)
+    }
+
+    pub(crate) fn accepts(&self, states: States) -> bool {
+        states & (1 << self.elements.len()) != 0
+    }
+
+    /// Adds the states reachable by matching `*`s against nothing.
+    fn closure(&self, mut states: States) -> States {
+        for (index, element) in self.elements.iter().enumerate() {
+            if *element == Element::AnyRun && states & (1 << index) != 0 {
+                states |= 1 << (index + 1);
+            }
+        }
+        states
+    }
+}
+
+/// A multiset of letters, plus blanks that can stand for any letter.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LetterBag {
+    /// The number of copies of each letter, by index in the alphabet.
+    pub(crate) counts: Vec<u32>,
+    pub(crate) blanks: u32,
+}
+
+impl LetterBag {
+    /// Parses letters such as `"RETINAS?"`, where `?` (or ` `) is a blank. Letters are
+    /// normalized with `normalizer`.
+    pub fn parse(text: &str, normalizer: &Normalizer) -> Result<LetterBag, String> {
+        let mut counts = vec![0; normalizer.alphabet().letters().len()];
+        let mut blanks = 0;
+        for c in text.chars() {
+            if c == '?' || c == ' ' {
+                blanks += 1;
+                continue;
+            }
+            let normalized = normalizer.normalize(&c.to_string());
+            if normalized.is_empty() {
+                return Err(format!("{:?} is not a letter or blank.", c));
+            }
+            for letter in normalized.chars() {
+                if let Some(index) = normalizer.alphabet().index_of(letter) {
+                    counts[index] += 1;
+                }
+            }
+        }
+        Ok(LetterBag { counts, blanks })
+    }
+
+    /// The number of letters and blanks in the bag.
+    pub fn len(&self) -> usize {
+        (self.counts.iter().sum::<u32>() + self.blanks) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn blanks(&self) -> usize {
+        self.blanks as usize
+    }
+}
+
+/// The letters that can appear at each position of a partially filled word, given
+/// the words that match it, and optionally how many of those words put each letter
+/// there. Built by `WordIndex::letter_options` and `WordIndex::letter_counts`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LetterOptions {
+    letters: Vec<char>,
+    /// For each position, a bitmask over the alphabet.
+    masks: Vec<u64>,
+    /// For each position, a count for each letter of the alphabet, if requested.
+    counts: Option<Vec<Vec<usize>>>,
+}
+
+impl LetterOptions {
+    pub(crate) fn new(normalizer: &Normalizer, length: usize, with_counts: bool) -> LetterOptions {
+        let letters = normalizer.alphabet().letters().to_vec();
+        LetterOptions {
+            counts: if with_counts {
+                Some(vec![vec![0; letters.len()]; length])
+            } else {
+                None
+            },
+            letters,
+            masks: vec![0; length],
+        }
+    }
+
+    /// Records `matches` words with alphabet index `letter` at `position`.
+    pub(crate) fn add(&mut self, position: usize, letter: usize, matches: usize) {
+        self.masks[position] |= 1 << letter;
+        if let Some(counts) = self.counts.as_mut() {
+            counts[position][letter] += matches;
+        }
+    }
+
+    /// The number of positions, i.e. the length of the pattern.
+    pub fn len(&self) -> usize {
+        self.masks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.masks.is_empty()
+    }
+
+    /// Returns whether no word matches the pattern.
+    pub fn is_dead(&self) -> bool {
+        self.masks.contains(&0)
+    }
+
+    /// The letters possible at `position`, as a bitmask over the alphabet.
+    pub fn mask(&self, position: usize) -> u64 {
+        self.masks[position]
+    }
+
+    /// The letters possible at `position`, in alphabet order.
+    pub fn letters(&self, position: usize) -> impl Iterator<Item = char> + '_ {
+        let mask = self.masks[position];
+        self.letters
+            .iter()
+            .enumerate()
+            .filter(move |(index, _)| mask & (1 << index) != 0)
+            .map(|(_, letter)| *letter)
+    }
+
+    pub fn contains(&self, position: usize, letter: char) -> bool {
+        self.index_of(letter)
+            .is_some_and(|index| self.masks[position] & (1 << index) != 0)
+    }
+
+    /// The number of matching words with `letter` at `position`, or `None` if counts
+    /// were not requested.
+    pub fn count(&self, position: usize, letter: char) -> Option<usize> {
+        let counts = self.counts.as_ref()?;
+        Some(
+            self.index_of(letter)
+                .map_or(0, |index| counts[position][index]),
+        )
+    }
+
+    fn index_of(&self, letter: char) -> Option<usize> {
+        self.letters
+            .iter()
+            .position(|candidate| *candidate == letter)
+    }
+}
+
+/// Returns every word in `trie` matching `pattern`, highest scoring first.
+///
+/// ```
+/// use xwords::{search::search, trie::Trie};
+///
+/// let trie = Trie::build_scored(vec![
+///     (String::from("BASS"), 40),
+///     (String::from("BOSS"), 60),
+///     (String::from("BASSES"), 50),
+/// ]);
+///
+/// let matches = search(&trie, "B[^A]SS").unwrap();
+/// assert_eq!(matches, vec![(String::from("BOSS"), 60)]);
+/// assert_eq!(search(&trie, "BASS*").unwrap().len(), 2);
+/// ```
+pub fn search(trie: &Trie, pattern: &str) -> Result<Vec<(String, Score)>, String> {
+    let pattern = Pattern::parse(pattern, trie.normalizer())?;
+    Ok(trie.search(&pattern))
+}
+
+/// Returns every word in `trie` that can be spelled with `letters`, highest scoring
+/// first. Each letter can be used once and each `?` stands for any letter. Without
+/// a `length`, words must use every letter, i.e. be anagrams of `letters`; with one,
+/// they must be that long.
+///
+/// ```
+/// use xwords::{search::anagrams, trie::Trie};
+///
+/// let trie = Trie::build(vec![
+///     String::from("STARE"),
+///     String::from("TEARS"),
+///     String::from("TEAR"),
+///     String::from("TREATS"),
+/// ]);
+///
+/// assert_eq!(anagrams(&trie, "RATES", None).unwrap().len(), 2);
+/// assert_eq!(anagrams(&trie, "RATES", Some(4)).unwrap().len(), 1);
+/// assert_eq!(anagrams(&trie, "RATES?", None).unwrap().len(), 1);
+/// ```
+pub fn anagrams(
+    trie: &Trie,
+    letters: &str,
+    length: Option<usize>,
+) -> Result<Vec<(String, Score)>, String> {
+    let letters = LetterBag::parse(letters, trie.normalizer())?;
+    Ok(trie.anagrams(&letters, length))
+}
+
+/// Returns every word in `trie` spelled only with `letters`, which may be reused
+/// any number of times, highest scoring first. With a `length`, only words of that
+/// length are returned.
+pub fn letter_bank(
+    trie: &Trie,
+    letters: &str,
+    length: Option<usize>,
+) -> Result<Vec<(String, Score)>, String> {
+    let letters = LetterBag::parse(letters, trie.normalizer())?;
+    if letters.blanks() > 0 {
+        return Err(String::from("Letter banks cannot contain blanks."));
+    }
+    Ok(trie.letter_bank(&letters, length))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        trie::{Trie, DEFAULT_SCORE},
+        wordlist::normalize::Normalizer,
+    };
+
+    use super::{anagrams, letter_bank, search, Element, LetterBag, Pattern};
+
+    #[test]
+    fn parse_works() {
+        let normalizer = Normalizer::default();
+
+        assert_eq!(
+            Pattern::parse("b?[^ae]*", &normalizer).unwrap().elements(),
+            &[
+                Element::Letter('B'),
+                Element::Any,
+                Element::Class {
+                    letters: vec!['A', 'E'],
+                    negated: true
+                },
+                Element::AnyRun,
+            ]
+        );
+        assert!(Pattern::parse("[AE", &normalizer).is_err());
+        assert!(Pattern::parse("[]", &normalizer).is_err());
+        assert!(Pattern::parse("A1", &normalizer).is_err());
+        assert!(Pattern::parse("", &normalizer).is_err());
+    }
+
+    #[test]
+    fn matches_works() {
+        let normalizer = Normalizer::default();
+        let pattern = Pattern::parse("*ING", &normalizer).unwrap();
+
+        assert!(pattern.matches("ING"));
+        assert!(pattern.matches("SINGING"));
+        assert!(!pattern.matches("INGS"));
+        assert_eq!(pattern.min_length(), 3);
+        assert_eq!(pattern.max_length(), None);
+
+        let pattern = Pattern::parse("[AEIOU]??E", &normalizer).unwrap();
+        assert!(pattern.matches("ABLE"));
+        assert!(!pattern.matches("BALE"));
+        assert_eq!(pattern.max_length(), Some(4));
+    }
+
+    #[test]
+    fn search_works() {
+        let trie = Trie::build_scored(vec![
+            (String::from("SING"), 30),
+            (String::from("SINGING"), 60),
+            (String::from("RING"), 60),
+            (String::from("INGOT"), 50),
+        ]);
+
+        assert_eq!(
+            search(&trie, "*ING").unwrap(),
+            vec![
+                (String::from("RING"), 60),
+                (String::from("SINGING"), 60),
+                (String::from("SING"), 30),
+            ]
+        );
+        assert_eq!(search(&trie, "*ING*").unwrap().len(), 4);
+        assert_eq!(search(&trie, "?ING").unwrap().len(), 2);
+        assert!(search(&trie, "[").is_err());
+    }
+
+    #[test]
+    fn letter_bag_works() {
+        let normalizer = Normalizer::default();
+        let bag = LetterBag::parse("Ææ?", &normalizer).unwrap();
+
+        assert_eq!(bag.len(), 5);
+        assert_eq!(bag.blanks(), 1);
+        assert_eq!(bag.counts[0], 2);
+        assert!(LetterBag::parse("A1", &normalizer).is_err());
+    }
+
+    #[test]
+    fn anagrams_work() {
+        let trie = Trie::build_scored(vec![
+            (String::from("LISTEN"), 40),
+            (String::from("SILENT"), 60),
+            (String::from("ENLIST"), 50),
+            (String::from("TINSEL"), 50),
+            (String::from("LIST"), 50),
+            (String::from("LENT"), 50),
+            (String::from("LEST"), 50),
+            (String::from("LESS"), 50),
+        ]);
+
+        assert_eq!(
+            anagrams(&trie, "listen", None).unwrap(),
+            vec![
+                (String::from("SILENT"), 60),
+                (String::from("ENLIST"), 50),
+                (String::from("TINSEL"), 50),
+                (String::from("LISTEN"), 40),
+            ]
+        );
+        assert_eq!(anagrams(&trie, "LISTEN", Some(4)).unwrap().len(), 3);
+        assert_eq!(anagrams(&trie, "LISTE", Some(4)).unwrap().len(), 2);
+        assert_eq!(anagrams(&trie, "LS??", None).unwrap().len(), 3);
+        assert!(anagrams(&trie, "LIST", Some(5)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn letter_bank_works() {
+        let trie = Trie::build(vec![
+            String::from("LESS"),
+            String::from("LEST"),
+            String::from("SELLS"),
+            String::from("TESTS"),
+        ]);
+
+        assert_eq!(
+            letter_bank(&trie, "LSE", None).unwrap(),
+            vec![
+                (String::from("LESS"), DEFAULT_SCORE),
+                (String::from("SELLS"), DEFAULT_SCORE),
+            ]
+        );
+        assert_eq!(letter_bank(&trie, "LSET", Some(4)).unwrap().len(), 2);
+        assert!(letter_bank(&trie, "LS?", None).is_err());
+    }
+}
@@ -2,49 +2,534 @@
 A data structure that provides efficient lookup of partially filled words.
 */
 
-use crate::File;
+use crate::{
+    index::{next_generation, serialized},
+    search::{LetterBag, LetterOptions, Pattern, States},
+    wordlist::{normalize::Normalizer, read_tagged_words, tags::TagSet, WordListFormat},
+    File,
+};
 use rustc_hash::FxHashMap;
-use serde::{Deserialize, Serialize};
-use std::fmt;
-#[derive(Clone, Serialize, Deserialize)]
-pub struct TrieNode {
-    contents: Option<char>,
-    children: FxHashMap<char, TrieNode>,
-    is_terminal: bool,
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+/// How desirable a word is as an entry; higher is better.
+pub type Score = u32;
+
+/// The score given to words loaded from lists that do not include scores.
+pub const DEFAULT_SCORE: Score = 50;
+
+/// A node of a `Trie`. Every node lives in the trie's arena: the children of a node
+/// are stored next to each other, in alphabet order, starting at `first_child`, and
+/// `children` has a bit set for the alphabet index of each of them.
+#[derive(Clone, Copy)]
+struct TrieNode {
+    children: u64,
+    first_child: u32,
+    /// The score of the word ending at this node, if there is one.
+    score: Option<Score>,
 }
 
 impl TrieNode {
-    fn add_sequence(mut self, chars: &str) -> TrieNode {
-        match chars.as_bytes().get(0) {
-            Some(val) => {
-                match self.children.remove_entry(&(*val as char)) {
-                    Some((_, child)) => {
-                        self.children
-                            .insert(*val as char, child.add_sequence(&chars[1..]));
-                    }
-                    None => {
-                        let tmp = TrieNode {
-                            children: FxHashMap::default(),
-                            contents: Some(*val as char),
-                            is_terminal: false,
-                        };
-                        // create child and iterate on it
-                        self.children
-                            .insert(*val as char, tmp.add_sequence(&chars[1..]));
-                    }
-                }
+    const EMPTY: TrieNode = TrieNode {
+        children: 0,
+        first_child: 0,
+        score: None,
+    };
+
+    /// Returns the arena index of the child for alphabet index `letter`.
+    fn child(&self, letter: usize) -> Option<usize> {
+        let bit = 1 << letter;
+        if self.children & bit == 0 {
+            return None;
+        }
+        Some(self.first_child as usize + (self.children & (bit - 1)).count_ones() as usize)
+    }
+
+    /// Iterates over the alphabet index and arena index of each child.
+    fn children(&self) -> Children {
+        Children {
+            remaining: self.children,
+            next: self.first_child as usize,
+        }
+    }
+}
+
+struct Children {
+    remaining: u64,
+    next: usize,
+}
+
+impl Iterator for Children {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let letter = self.remaining.trailing_zeros() as usize;
+        self.remaining &= self.remaining - 1;
+        self.next += 1;
+        Some((letter, self.next - 1))
+    }
+}
+
+/// The nodes of a `Trie`, with a separate root for each word length so that a lookup
+/// only visits words of the pattern's length.
+#[derive(Clone)]
+struct Arena {
+    nodes: Vec<TrieNode>,
+    /// The arena index of the root for words of each length.
+    roots: Vec<u32>,
+}
+
+impl Arena {
+    fn new() -> Arena {
+        Arena {
+            nodes: vec![],
+            roots: vec![],
+        }
+    }
+
+    fn root(&self, length: usize) -> Option<usize> {
+        self.roots.get(length).map(|root| *root as usize)
+    }
+
+    /// Returns the root for words of `length`, adding roots as needed.
+    fn root_or_insert(&mut self, length: usize) -> usize {
+        while self.roots.len() <= length {
+            self.roots.push(self.nodes.len() as u32);
+            self.nodes.push(TrieNode::EMPTY);
+        }
+        self.roots[length] as usize
+    }
+
+    /// Adds an empty child to `parent` and returns its arena index. Unless they are
+    /// already at the end of the arena, the parent's existing children are copied
+    /// there so that they stay contiguous; `compact` reclaims the space they leave.
+    fn add_child(&mut self, parent: usize, letter: usize) -> usize {
+        let TrieNode {
+            children,
+            first_child,
+            ..
+        } = self.nodes[parent];
+        let count = children.count_ones() as usize;
+        let mut first_child = first_child as usize;
+        if count == 0 || first_child + count != self.nodes.len() {
+            let end = self.nodes.len();
+            self.nodes
+                .extend_from_within(first_child..first_child + count);
+            first_child = end;
+        }
+
+        let bit = 1 << letter;
+        let index = first_child + (children & (bit - 1)).count_ones() as usize;
+        self.nodes.insert(index, TrieNode::EMPTY);
+        self.nodes[parent].children |= bit;
+        self.nodes[parent].first_child = first_child as u32;
+        index
+    }
+
+    /// Returns the arena indexes of every node in breadth-first order, starting with
+    /// the roots.
+    fn breadth_first(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = self.roots.iter().map(|root| *root as usize).collect();
+        let mut next = 0;
+        while next < order.len() {
+            order.extend(self.nodes[order[next]].children().map(|(_, child)| child));
+            next += 1;
+        }
+        order
+    }
+
+    /// Removes the child of `parent` for alphabet index `letter`, which must exist and
+    /// have no children of its own. Later siblings move down to close the gap.
+    fn remove_child(&mut self, parent: usize, letter: usize) {
+        let TrieNode {
+            children,
+            first_child,
+            ..
+        } = self.nodes[parent];
+        let end = first_child as usize + children.count_ones() as usize;
+        let index = self.nodes[parent]
+            .child(letter)
+            .expect("only existing children are removed");
+        self.nodes.copy_within(index + 1..end, index);
+        self.nodes[parent].children &= !(1 << letter);
+    }
+
+    /// Rebuilds the arena in breadth-first order, dropping nodes left behind by
+    /// `add_child` and `remove_child`. Afterwards, the roots are the first nodes of the arena.
+    fn compact(&mut self) {
+        let mut nodes: Vec<TrieNode> = self
+            .breadth_first()
+            .into_iter()
+            .map(|index| self.nodes[index])
+            .collect();
+        let mut first_child = self.roots.len();
+        for node in nodes.iter_mut() {
+            node.first_child = first_child as u32;
+            first_child += node.children.count_ones() as usize;
+        }
+        self.roots = (0..self.roots.len() as u32).collect();
+        self.nodes = nodes;
+    }
+
+    /// Returns the `children` and `score` of every node in breadth-first order. The
+    /// roots come first, and the children of a node directly follow the children of
+    /// the nodes before it, so the arena can be rebuilt without `first_child` links.
+    fn packed(&self) -> Vec<(u64, Option<Score>)> {
+        self.breadth_first()
+            .into_iter()
+            .map(|index| (self.nodes[index].children, self.nodes[index].score))
+            .collect()
+    }
+}
+
+/// An arena is serialized `packed`, and its `first_child` links are recomputed on
+/// load.
+impl Serialize for Arena {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.roots.len(), self.packed()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Arena {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Arena, D::Error> {
+        let (root_count, packed): (usize, Vec<(u64, Option<Score>)>) =
+            Deserialize::deserialize(deserializer)?;
+        let mut first_child = root_count;
+        let nodes: Vec<TrieNode> = packed
+            .into_iter()
+            .map(|(children, score)| {
+                let node = TrieNode {
+                    children,
+                    first_child: first_child as u32,
+                    score,
+                };
+                first_child += children.count_ones() as usize;
+                node
+            })
+            .collect();
+        if root_count > nodes.len() || first_child != nodes.len() {
+            return Err(D::Error::custom("trie nodes do not form a tree"));
+        }
+        Ok(Arena {
+            nodes,
+            roots: (0..root_count as u32).collect(),
+        })
+    }
+}
+
+/// Orders lookup results by descending score, then alphabetically.
+fn rank(result: &mut [(String, Score)]) {
+    result.sort_by(|(a, a_score), (b, b_score)| b_score.cmp(a_score).then_with(|| a.cmp(b)));
+}
+
+/// The environment variable that overrides the word list used by `Trie::load_default`.
+pub const WORDLIST_ENV_VAR: &str = "XWORDS_WORDLIST";
+
+const BUNDLED_WORDLIST: &str = include_str!(concat!(env!("OUT_DIR"), "/default_wordlist.txt"));
+
+/// Marks characters outside the alphabet in `Trie::letter_indices`.
+const NOT_A_LETTER: u8 = u8::MAX;
+
+/// A trie stored as a flat arena of nodes, with children indexed by a bitmask over
+/// the trie's alphabet and a separate root for each word length.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Trie {
+    arena: Arena,
+    /// The alphabet index of each ASCII character, or `NOT_A_LETTER`.
+    letter_indices: Vec<u8>,
+    normalizer: Normalizer,
+    /// Display forms of words whose source differs from their normalization by more
+    /// than case, e.g. `BILLIEJEANKING` => `Billie Jean King`.
+    displays: FxHashMap<String, String>,
+    /// The tags of words that have any.
+    tags: FxHashMap<String, TagSet>,
+    /// See `WordIndex::generation`.
+    #[serde(skip, default = "next_generation")]
+    generation: u64,
+}
+
+impl fmt::Display for Trie {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        for root in self.arena.roots.iter() {
+            if self.arena.nodes[*root as usize].children != 0 {
+                self.display_helper(f, *root as usize, '*', 1, true)?;
             }
-            None => {
-                self.is_terminal = true;
+        }
+        Ok(())
+    }
+}
+
+impl Trie {
+    /// Loads the default word list. If the `XWORDS_WORDLIST` environment variable is
+    /// set, the list is loaded from that path (see `Trie::load`); otherwise the list
+    /// bundled with the crate is used.
+    pub fn load_default() -> Result<Trie, String> {
+        match std::env::var_os(WORDLIST_ENV_VAR) {
+            Some(path) => Trie::load(Path::new(&path)),
+            None => Trie::load_bundled(),
+        }
+    }
+
+    /// Builds a `Trie` from the word list embedded in the crate at compile time, which
+    /// is generated from `wordlist.json`.
+    pub fn load_bundled() -> Result<Trie, String> {
+        Trie::from_reader(BUNDLED_WORDLIST.as_bytes(), WordListFormat::Text)
+    }
+
+    /// Creates an empty `Trie` whose words will be normalized with `normalizer`.
+    pub fn new(normalizer: Normalizer) -> Trie {
+        let mut letter_indices = vec![NOT_A_LETTER; 128];
+        for (index, letter) in normalizer.alphabet().letters().iter().enumerate() {
+            letter_indices[*letter as usize] = index as u8;
+        }
+        Trie {
+            arena: Arena::new(),
+            letter_indices,
+            normalizer,
+            displays: FxHashMap::default(),
+            tags: FxHashMap::default(),
+            generation: next_generation(),
+        }
+    }
+
+    /// Builds a `Trie` from unscored words, giving each of them `DEFAULT_SCORE`.
+    pub fn build(words: Vec<String>) -> Trie {
+        Trie::build_scored(
+            words
+                .into_iter()
+                .map(|word| (word, DEFAULT_SCORE))
+                .collect(),
+        )
+    }
+
+    /// Builds a `Trie` from scored words. Words are normalized with the default
+    /// `Normalizer`, and if a word appears more than once, the last score wins.
+    pub fn build_scored(words: Vec<(String, Score)>) -> Trie {
+        let mut trie = Trie::new(Normalizer::default());
+        for (word, score) in words.iter() {
+            trie.insert(word, *score);
+        }
+        trie.arena.compact();
+        trie
+    }
+
+    /// Like `build_scored`, but for words with tags.
+    pub fn build_tagged(words: Vec<(String, Score, TagSet)>) -> Trie {
+        let mut trie = Trie::new(Normalizer::default());
+        for (word, score, tags) in words.iter() {
+            trie.insert_tagged(word, *score, *tags);
+        }
+        trie.arena.compact();
+        trie
+    }
+
+    /// Builds a `Trie` from a word list, inserting each entry as it is parsed so that
+    /// large lists never have to be held in memory as a whole.
+    pub fn from_reader<R: BufRead>(reader: R, format: WordListFormat) -> Result<Trie, String> {
+        let mut trie = Trie::new(Normalizer::default());
+        trie.read_from(reader, format)?;
+        Ok(trie)
+    }
+
+    /// Adds the entries of a word list to this `Trie`. Returns the number of entries
+    /// read.
+    pub fn read_from<R: BufRead>(
+        &mut self,
+        reader: R,
+        format: WordListFormat,
+    ) -> Result<usize, String> {
+        let count = read_tagged_words(reader, format, |word, score, tags| {
+            self.insert_tagged(&word, score, tags);
+        })?;
+        self.arena.compact();
+        Ok(count)
+    }
+
+    /// Loads a word list from disk, choosing the format by extension. A `.bincode`
+    /// file is read as a `Trie` saved by `index::serialized::save`, and files with an
+    /// unrecognized extension are read as `WordListFormat::Dict`. `.xwi` indexes are
+    /// rejected, since they are read with `MappedIndex::open` instead.
+    pub fn load(path: &Path) -> Result<Trie, String> {
+        if path.extension().is_some_and(|extension| extension == "xwi") {
+            return Err(format!(
+                "{} is a memory-mapped index, which only filling can use (xwords --input \
+                 GRID --wordlist {}); pass the word list it was built from instead.",
+                path.display(),
+                path.display()
+            ));
+        }
+        let file = File::open(path)
+            .map_err(|err| format!("Failed to open {}: {}", path.display(), err))?;
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "bincode")
+        {
+            return serialized::read(BufReader::new(file))
+                .map_err(|err| format!("Failed to load trie from {}: {}", path.display(), err));
+        }
+        let format = WordListFormat::from_path(path).unwrap_or(WordListFormat::Dict);
+        Trie::from_reader(BufReader::new(file), format)
+            .map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn normalizer(&self) -> &Normalizer {
+        &self.normalizer
+    }
+
+    /// Returns the form `word` should be shown in, e.g. `Billie Jean King` rather
+    /// than `BILLIEJEANKING`. Words without a distinct display form are returned as is.
+    pub fn display<'s>(&'s self, word: &'s str) -> &'s str {
+        self.displays.get(word).map_or(word, String::as_str)
+    }
+
+    /// Returns the tags of `word`, which are empty for words without any and words
+    /// that are not in the `Trie`.
+    pub fn tags(&self, word: &str) -> TagSet {
+        self.tags.get(word).copied().unwrap_or_default()
+    }
+
+    /// Returns the number of roots and the packed nodes of the `Trie`, for writing
+    /// other index formats.
+    pub(crate) fn packed_nodes(&self) -> (usize, Vec<(u64, Option<Score>)>) {
+        (self.arena.roots.len(), self.arena.packed())
+    }
+
+    /// Returns the tags and display form of every word that has either, in sorted
+    /// order.
+    pub(crate) fn annotations(&self) -> BTreeMap<&str, (TagSet, Option<&str>)> {
+        let mut result = BTreeMap::new();
+        for (word, tags) in self.tags.iter() {
+            result.insert(word.as_str(), (*tags, None));
+        }
+        for (word, display) in self.displays.iter() {
+            result
+                .entry(word.as_str())
+                .or_insert((TagSet::default(), None))
+                .1 = Some(display.as_str());
+        }
+        result
+    }
+
+    /// Counts the words in the `Trie`. This walks every node.
+    pub fn word_count(&self) -> usize {
+        self.arena
+            .breadth_first()
+            .into_iter()
+            .filter(|index| self.arena.nodes[*index].score.is_some())
+            .count()
+    }
+
+    /// Changes whenever words are added or removed. See `WordIndex::generation`.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Adds `word`, normalizing it first, or changes its score if it is already
+    /// present. Returns its previous score.
+    ///
+    /// Space left behind by inserts is reclaimed when a word list is read or
+    /// `shrink_to_fit` is called.
+    pub fn insert(&mut self, word: &str, score: Score) -> Option<Score> {
+        self.insert_tagged(word, score, TagSet::default())
+    }
+
+    /// Like `insert`, but also replaces the tags of `word` with `tags`.
+    pub fn insert_tagged(&mut self, word: &str, score: Score, tags: TagSet) -> Option<Score> {
+        let normalized = self.normalizer.normalize(word);
+        if normalized.is_empty() {
+            return None;
+        }
+        self.generation = next_generation();
+        match self.normalizer.display_form(word, &normalized) {
+            Some(display) => self.displays.insert(normalized.clone(), display),
+            None => self.displays.remove(&normalized),
+        };
+        if tags.is_empty() {
+            self.tags.remove(&normalized);
+        } else {
+            self.tags.insert(normalized.clone(), tags);
+        }
+
+        let mut node = self.arena.root_or_insert(normalized.chars().count());
+        for c in normalized.chars() {
+            let letter = self
+                .letter_index(c)
+                .expect("normalized words only contain letters of the alphabet");
+            node = match self.arena.nodes[node].child(letter) {
+                Some(child) => child,
+                None => self.arena.add_child(node, letter),
+            };
+        }
+        self.arena.nodes[node].score.replace(score)
+    }
+
+    /// Removes `word`, normalizing it first, along with any branches left without
+    /// words. Returns its score if it was present.
+    pub fn remove(&mut self, word: &str) -> Option<Score> {
+        let normalized = self.normalizer.normalize(word);
+        let mut path = vec![self.arena.root(normalized.chars().count())?];
+        let mut letters = Vec::with_capacity(normalized.len());
+        for c in normalized.chars() {
+            let letter = self.letter_index(c)?;
+            path.push(self.arena.nodes[*path.last().unwrap()].child(letter)?);
+            letters.push(letter);
+        }
+        let score = self.arena.nodes[*path.last().unwrap()].score.take()?;
+        self.displays.remove(&normalized);
+        self.tags.remove(&normalized);
+        self.generation = next_generation();
+
+        while let (Some(node), Some(letter)) = (path.pop(), letters.pop()) {
+            let TrieNode {
+                children, score, ..
+            } = self.arena.nodes[node];
+            if children != 0 || score.is_some() {
+                break;
             }
+            self.arena.remove_child(*path.last().unwrap(), letter);
         }
+        Some(score)
+    }
 
-        self
+    /// Returns whether `word`, once normalized, is in the `Trie`.
+    pub fn contains(&self, word: &str) -> bool {
+        self.score(&self.normalizer.normalize(word)).is_some()
+    }
+
+    /// Reclaims space left behind by `insert` and `remove`.
+    pub fn shrink_to_fit(&mut self) {
+        self.arena.compact();
+        self.arena.nodes.shrink_to_fit();
+    }
+
+    fn letter_index(&self, c: char) -> Option<usize> {
+        match self.letter_indices.get(c as usize) {
+            Some(index) if *index != NOT_A_LETTER => Some(*index as usize),
+            _ => None,
+        }
+    }
+
+    fn letter(&self, index: usize) -> char {
+        self.normalizer.alphabet().letters()[index]
+    }
+
+    fn child(&self, node: usize, c: char) -> Option<usize> {
+        self.arena.nodes[node].child(self.letter_index(c)?)
     }
 
     fn display_helper(
         &self,
         f: &mut std::fmt::Formatter<'_>,
+        node: usize,
+        contents: char,
         depth: usize,
         first_child: bool,
     ) -> std::result::Result<(), std::fmt::Error> {
@@ -55,213 +540,593 @@ impl TrieNode {
         } else {
             write!(f, "\t")?;
         }
-        write!(f, "{}", self.contents.unwrap_or('*'))?;
+        write!(f, "{}", contents)?;
 
-        if self.is_terminal {
+        if self.arena.nodes[node].score.is_some() {
             write!(f, "'")?;
         }
 
-        if self.children.is_empty() {
+        if self.arena.nodes[node].children == 0 {
             return writeln!(f);
         }
 
-        for (index, key) in self.children.keys().into_iter().enumerate() {
-            self.children
-                .get(key)
-                .unwrap()
-                .display_helper(f, depth + 1, index == 0)?;
+        for (index, (letter, child)) in self.arena.nodes[node].children().enumerate() {
+            self.display_helper(f, child, self.letter(letter), depth + 1, index == 0)?;
         }
 
         Ok(())
     }
 
-    fn words<T: Iterator<Item = char> + Clone>(
+    /// Visits the words below `node` matching the rest of `pattern`. Returns false if
+    /// `visit` asked to stop.
+    fn visit_from<T: Iterator<Item = char> + Clone, F: FnMut(&str, Score) -> bool>(
         &self,
+        node: usize,
         mut pattern: T,
         partial: &mut String,
-        result: &mut Vec<String>,
-    ) {
-        if self.contents.is_some() {
-            partial.push(self.contents.unwrap());
-        }
-
+        min_score: Score,
+        visit: &mut F,
+    ) -> bool {
         match pattern.next() {
-            Some(new_char) => {
-                if new_char == ' ' {
-                    for child in self.children.values() {
-                        child.words(pattern.clone(), partial, result);
-                    }
-                } else {
-                    if let Some(child) = self.children.get(&new_char) {
-                        child.words(pattern, partial, result);
+            Some(' ') => {
+                for (letter, child) in self.arena.nodes[node].children() {
+                    partial.push(self.letter(letter));
+                    let go_on = self.visit_from(child, pattern.clone(), partial, min_score, visit);
+                    partial.pop();
+                    if !go_on {
+                        return false;
                     }
                 }
+                true
             }
-            None => {
-                if self.is_terminal {
-                    result.push(partial.clone());
+            Some(c) => match self.child(node, c) {
+                Some(child) => {
+                    partial.push(c);
+                    let go_on = self.visit_from(child, pattern, partial, min_score, visit);
+                    partial.pop();
+                    go_on
                 }
+                None => true,
+            },
+            None => match self.arena.nodes[node].score {
+                Some(score) if score >= min_score => visit(partial, score),
+                _ => true,
+            },
+        }
+    }
+
+    /// Records the letters of the matches below `node` in `result`, starting at
+    /// `position`, and returns the number of matches.
+    fn letter_options_from<T: Iterator<Item = char> + Clone>(
+        &self,
+        node: usize,
+        mut pattern: T,
+        position: usize,
+        min_score: Score,
+        result: &mut LetterOptions,
+    ) -> usize {
+        let c = match pattern.next() {
+            Some(c) => c,
+            None => {
+                return self.arena.nodes[node]
+                    .score
+                    .is_some_and(|score| score >= min_score) as usize
+            }
+        };
+        let mut total = 0;
+        let mut visit = |letter: usize, child: usize, result: &mut LetterOptions| {
+            let matches =
+                self.letter_options_from(child, pattern.clone(), position + 1, min_score, result);
+            if matches > 0 {
+                result.add(position, letter, matches);
+                total += matches;
+            }
+        };
+        if c == ' ' {
+            for (letter, child) in self.arena.nodes[node].children() {
+                visit(letter, child, result);
+            }
+        } else if let Some(letter) = self.letter_index(c) {
+            if let Some(child) = self.arena.nodes[node].child(letter) {
+                visit(letter, child, result);
             }
         }
+        total
+    }
 
-        if self.contents.is_some() {
-            partial.pop();
+    pub fn words<T: Iterator<Item = char> + Clone>(&self, pattern: T) -> Vec<String> {
+        self.words_with_min_score(pattern, 0)
+    }
+
+    /// Like `words`, but only returns words scoring at least `min_score`.
+    pub fn words_with_min_score<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> Vec<String> {
+        let mut result = Vec::with_capacity(4);
+        self.visit_matches(pattern, min_score, |word, _| {
+            result.push(String::from(word));
+            true
+        });
+        result
+    }
+
+    /// Calls `visit` with each word matching `pattern` that scores at least
+    /// `min_score`, and its score, in the order `words` returns them. The word is
+    /// borrowed from a buffer the `Trie` reuses, so nothing is allocated per match.
+    /// Stops as soon as `visit` returns false, and returns whether every match was
+    /// visited.
+    pub fn visit_matches<T: Iterator<Item = char> + Clone, F: FnMut(&str, Score) -> bool>(
+        &self,
+        pattern: T,
+        min_score: Score,
+        mut visit: F,
+    ) -> bool {
+        let mut partial = String::with_capacity(16);
+        match self.arena.root(pattern.clone().count()) {
+            Some(root) => self.visit_from(root, pattern, &mut partial, min_score, &mut visit),
+            None => true,
         }
     }
 
-    pub fn is_viable<T: Iterator<Item = char> + Clone>(&self, mut chars: T) -> bool {
-        match chars.next() {
-            None => self.is_terminal,
+    /// Counts the words `words_with_min_score` would return without building them.
+    pub fn count_matches<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> usize {
+        let mut count = 0;
+        self.visit_matches(pattern, min_score, |_, _| {
+            count += 1;
+            true
+        });
+        count
+    }
 
-            Some(c) => {
-                if c == ' ' {
-                    for child in self.children.values() {
-                        if child.is_viable(chars.clone()) {
-                            return true;
-                        }
-                    }
-                    false
-                } else {
-                    match self.children.get(&c) {
-                        None => false,
-                        Some(child) => child.is_viable(chars),
-                    }
-                }
+    /// Returns the letters that the words matching `pattern` and scoring at least
+    /// `min_score` put at each position, in a single traversal.
+    pub fn letter_options<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> LetterOptions {
+        self.collect_letter_options(pattern, min_score, false)
+    }
+
+    /// Like `letter_options`, but also counts the matching words with each letter at
+    /// each position.
+    pub fn letter_counts<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+    ) -> LetterOptions {
+        self.collect_letter_options(pattern, min_score, true)
+    }
+
+    pub(crate) fn collect_letter_options<T: Iterator<Item = char> + Clone>(
+        &self,
+        pattern: T,
+        min_score: Score,
+        with_counts: bool,
+    ) -> LetterOptions {
+        let length = pattern.clone().count();
+        let mut result = LetterOptions::new(&self.normalizer, length, with_counts);
+        if let Some(root) = self.arena.root(length) {
+            self.letter_options_from(root, pattern, 0, min_score, &mut result);
+        }
+        result
+    }
+
+    /// Like `letter_options`, but each position is constrained by a bitmask over the
+    /// alphabet rather than a letter or a blank. Only words whose letters are all
+    /// within the masks of their positions match.
+    pub fn masked_letter_options(&self, masks: &[u64], min_score: Score) -> LetterOptions {
+        let mut result = LetterOptions::new(&self.normalizer, masks.len(), false);
+        if let Some(root) = self.arena.root(masks.len()) {
+            self.masked_options_from(root, masks, 0, min_score, true, &mut result);
+        }
+        result
+    }
+
+    /// Records the letters of the matches below `node` in `result`, starting at
+    /// `position`. Once the letters of every later position are known, subtrees that
+    /// cannot add a letter are skipped, so the return value, whether any word matches,
+    /// is only exact if `needed`.
+    fn masked_options_from(
+        &self,
+        node: usize,
+        masks: &[u64],
+        position: usize,
+        min_score: Score,
+        needed: bool,
+        result: &mut LetterOptions,
+    ) -> bool {
+        if position == masks.len() {
+            return self.arena.nodes[node]
+                .score
+                .is_some_and(|score| score >= min_score);
+        }
+        let complete = (position + 1..masks.len()).all(|later| result.mask(later) == masks[later]);
+        let mut any = false;
+        for (letter, child) in self.arena.nodes[node].children() {
+            let bit = 1 << letter;
+            if masks[position] & bit == 0 {
+                continue;
+            }
+            let new = result.mask(position) & bit == 0;
+            let needed = new || (needed && !any);
+            if !needed && complete {
+                continue;
+            }
+            if self.masked_options_from(child, masks, position + 1, min_score, needed, result) {
+                result.add(position, letter, 1);
+                any = true;
             }
         }
+        any
     }
-}
 
-impl fmt::Display for TrieNode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        self.display_helper(f, 1, true)
+    pub fn is_viable<T: Iterator<Item = char> + Clone>(&self, chars: T) -> bool {
+        self.is_viable_with_min_score(chars, 0)
     }
-}
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct Trie {
-    pub root: TrieNode,
-}
+    /// Like `is_viable`, but only considers words scoring at least `min_score`.
+    pub fn is_viable_with_min_score<T: Iterator<Item = char> + Clone>(
+        &self,
+        chars: T,
+        min_score: Score,
+    ) -> bool {
+        // Stopping at the first match means there was one.
+        !self.visit_matches(chars, min_score, |_, _| false)
+    }
 
-impl fmt::Display for Trie {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        self.root.fmt(f)
+    /// Returns the score of `word`, or `None` if it is not in the `Trie`.
+    pub fn score(&self, word: &str) -> Option<Score> {
+        let mut node = self.arena.root(word.chars().count())?;
+        for c in word.chars() {
+            node = self.child(node, c)?;
+        }
+        self.arena.nodes[node].score
     }
-}
 
-impl Trie {
-    pub fn load_default() -> Result<Trie, String> {
-        let file = File::open("./trie.bincode").unwrap();
-        let load = bincode::deserialize_from::<File, Trie>(file);
-        load.map_err(|_| String::from("Failed to load trie."))
+    /// Returns every word matching `pattern` with its score, highest scoring first and
+    /// alphabetically among equal scores.
+    pub fn search(&self, pattern: &Pattern) -> Vec<(String, Score)> {
+        let max_length = pattern
+            .max_length()
+            .unwrap_or_else(|| self.arena.roots.len().saturating_sub(1));
+        let mut result = vec![];
+        let mut partial = String::new();
+        for length in pattern.min_length()..=max_length {
+            if let Some(root) = self.arena.root(length) {
+                self.search_from(root, pattern, pattern.start(), &mut partial, &mut result);
+            }
+        }
+        rank(&mut result);
+        result
     }
 
-    pub fn build(words: Vec<String>) -> Trie {
-        let mut root = TrieNode {
-            contents: None,
-            children: FxHashMap::default(),
-            is_terminal: false,
+    /// Returns every word that can be spelled with `letters`, using each letter at
+    /// most once and each blank for any letter. Words must be `length` letters long,
+    /// or use every letter and blank if `length` is `None`. Results are ordered like
+    /// `search`.
+    pub fn anagrams(&self, letters: &LetterBag, length: Option<usize>) -> Vec<(String, Score)> {
+        let mut result = vec![];
+        let length = length.unwrap_or_else(|| letters.len());
+        if length <= letters.len() {
+            if let Some(root) = self.arena.root(length) {
+                let mut counts = letters.counts.clone();
+                let mut partial = String::new();
+                self.anagrams_from(root, &mut counts, letters.blanks, &mut partial, &mut result);
+            }
+        }
+        rank(&mut result);
+        result
+    }
+
+    /// Returns every word spelled only with the letters in `letters`, each of which
+    /// may be used any number of times. Blanks are ignored. If `length` is given, only
+    /// words of that length are returned. Results are ordered like `search`.
+    pub fn letter_bank(&self, letters: &LetterBag, length: Option<usize>) -> Vec<(String, Score)> {
+        let allowed = letters
+            .counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .fold(0u64, |allowed, (letter, _)| allowed | 1 << letter);
+        let lengths = match length {
+            Some(length) => length..length + 1,
+            None => 1..self.arena.roots.len(),
         };
 
-        for word in words.iter() {
-            root = root.add_sequence(&word);
+        let mut result = vec![];
+        let mut partial = String::new();
+        for length in lengths {
+            if let Some(root) = self.arena.root(length) {
+                self.letter_bank_from(root, allowed, &mut partial, &mut result);
+            }
         }
+        rank(&mut result);
+        result
+    }
 
-        Trie { root }
+    fn anagrams_from(
+        &self,
+        node: usize,
+        counts: &mut [u32],
+        blanks: u32,
+        partial: &mut String,
+        result: &mut Vec<(String, Score)>,
+    ) {
+        if let Some(score) = self.arena.nodes[node].score {
+            result.push((partial.clone(), score));
+        }
+        for (letter, child) in self.arena.nodes[node].children() {
+            partial.push(self.letter(letter));
+            // Spending a letter is never worse than spending a blank on it.
+            if counts[letter] > 0 {
+                counts[letter] -= 1;
+                self.anagrams_from(child, counts, blanks, partial, result);
+                counts[letter] += 1;
+            } else if blanks > 0 {
+                self.anagrams_from(child, counts, blanks - 1, partial, result);
+            }
+            partial.pop();
+        }
     }
 
-    pub fn words<T: Iterator<Item = char> + Clone>(&self, pattern: T) -> Vec<String> {
-        let mut result = Vec::with_capacity(4);
-        let mut partial = String::with_capacity(4);
-        self.root.words(pattern, &mut partial, &mut result);
-        result
+    fn letter_bank_from(
+        &self,
+        node: usize,
+        allowed: u64,
+        partial: &mut String,
+        result: &mut Vec<(String, Score)>,
+    ) {
+        if let Some(score) = self.arena.nodes[node].score {
+            result.push((partial.clone(), score));
+        }
+        for (letter, child) in self.arena.nodes[node].children() {
+            if allowed & (1 << letter) != 0 {
+                partial.push(self.letter(letter));
+                self.letter_bank_from(child, allowed, partial, result);
+                partial.pop();
+            }
+        }
     }
 
-    pub fn is_viable<T: Iterator<Item = char> + Clone>(&self, chars: T) -> bool {
-        self.root.is_viable(chars)
+    fn search_from(
+        &self,
+        node: usize,
+        pattern: &Pattern,
+        states: States,
+        partial: &mut String,
+        result: &mut Vec<(String, Score)>,
+    ) {
+        if let Some(score) = self.arena.nodes[node].score {
+            if pattern.accepts(states) {
+                result.push((partial.clone(), score));
+            }
+        }
+        for (letter, child) in self.arena.nodes[node].children() {
+            let c = self.letter(letter);
+            let next = pattern.step(states, c);
+            if next != 0 {
+                partial.push(c);
+                self.search_from(child, pattern, next, partial, result);
+                partial.pop();
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::File;
-    use rustc_hash::FxHashMap;
+    use std::{collections::HashSet, path::Path};
 
-    use std::collections::HashSet;
+    use super::{Trie, DEFAULT_SCORE};
+    use crate::index::{bitset::BitsetIndex, WordIndex};
+    use crate::wordlist::{
+        normalize::Normalizer,
+        tags::{Tag, TagSet},
+        WordListFormat,
+    };
 
-    use super::{Trie, TrieNode};
+    #[test]
+    fn test_trie_load() {
+        let trie = Trie::load_bundled().unwrap();
+        assert!(trie.score("BEST").is_some());
+
+        assert!(Trie::load(Path::new("does/not/exist.dict")).is_err());
+        let err = Trie::load(Path::new("does/not/exist.xwi")).err().unwrap();
+        assert!(err.contains("--wordlist does/not/exist.xwi"), "{}", err);
+    }
 
     #[test]
-    #[ignore]
-    fn rebuild_serialized_trie() {
-        let file = File::open("wordlist.json").unwrap();
-        let words = serde_json::from_reader(file).expect("JSON was not well-formatted");
-        let trie = Trie::build(words);
-        let trie_file = File::create("trie.bincode").unwrap();
-        let trie_result = bincode::serialize_into(trie_file, &trie);
-        assert!(trie_result.is_ok());
+    fn display_works() {
+        let trie = Trie::build(vec![String::from("B"), String::from("CD")]);
+
+        assert_eq!(trie.to_string(), "\t*\tB'\n\t*\tC\tD'\n");
     }
 
     #[test]
-    fn test_trie_load() {
-        let file = File::open("./trie.bincode").unwrap();
-        let load = bincode::deserialize_from::<File, Trie>(file);
-        assert!(load.is_ok());
+    fn insert_works() {
+        let mut trie = Trie::new(Normalizer::default());
+        for word in ["BASS", "ASSET", "BASSET", "BAT", "AS", "BA"].iter() {
+            assert_eq!(trie.insert(word, DEFAULT_SCORE), None);
+        }
+        assert_eq!(trie.insert("bat", 70), Some(DEFAULT_SCORE));
+        let words = trie.words("   ".chars());
+        trie.arena.compact();
+
+        assert_eq!(words, vec![String::from("BAT")]);
+        assert_eq!(trie.words("   ".chars()), words);
+        assert_eq!(trie.words("  ".chars()), vec!["AS", "BA"]);
+        assert_eq!(trie.words("BASS  ".chars()), vec!["BASSET"]);
+        // A root for each length up to 6, then A, B, AS, BA; B, BA, BAT; B, BA, BAS,
+        // BASS; and so on.
+        assert_eq!(trie.arena.nodes.len(), 7 + 4 + 3 + 4 + 5 + 6);
+        assert_eq!(trie.arena.roots, (0..7).collect::<Vec<u32>>());
     }
 
     #[test]
-    fn display_works() {
-        let mut root = TrieNode {
-            contents: None,
-            children: FxHashMap::default(),
-            is_terminal: false,
-        };
+    fn remove_works() {
+        let mut trie = Trie::build(vec![
+            String::from("BASS"),
+            String::from("BASSET"),
+            String::from("BEST"),
+            String::from("Billie Jean King"),
+        ]);
+        let generation = trie.generation();
+        let nodes = trie.arena.nodes.len();
 
-        root.children.insert(
-            'b',
-            TrieNode {
-                contents: Some('b'),
-                children: FxHashMap::default(),
-                is_terminal: false,
-            },
+        assert_eq!(trie.remove("bass"), Some(DEFAULT_SCORE));
+        assert_eq!(trie.remove("BASS"), None);
+        assert_eq!(trie.remove("BAS"), None);
+        assert!(!trie.contains("BASS"));
+        assert!(trie.contains("best"));
+        assert_ne!(trie.generation(), generation);
+        assert_eq!(trie.words("B   ".chars()), vec!["BEST"]);
+
+        assert_eq!(trie.remove("Billie Jean King"), Some(DEFAULT_SCORE));
+        assert_eq!(trie.display("BILLIEJEANKING"), "BILLIEJEANKING");
+
+        trie.shrink_to_fit();
+        // BASS shares B with BEST, so only A, S, S go; BILLIEJEANKING goes entirely.
+        assert_eq!(trie.arena.nodes.len(), nodes - 3 - 14);
+        assert_eq!(trie.words("BASS  ".chars()), vec!["BASSET"]);
+    }
+
+    #[test]
+    fn visit_matches_works() {
+        let trie = Trie::build_scored(vec![
+            (String::from("BASS"), 60),
+            (String::from("BATS"), 40),
+            (String::from("BESS"), 20),
+            (String::from("BOSS"), 50),
+        ]);
+
+        let mut visited = vec![];
+        assert!(trie.visit_matches("B SS".chars(), 30, |word, score| {
+            visited.push((String::from(word), score));
+            true
+        }));
+        assert_eq!(
+            visited,
+            vec![(String::from("BASS"), 60), (String::from("BOSS"), 50)]
         );
 
-        let mut c = TrieNode {
-            contents: Some('c'),
-            children: FxHashMap::default(),
-            is_terminal: false,
-        };
+        let mut count = 0;
+        assert!(!trie.visit_matches("    ".chars(), 0, |_, _| {
+            count += 1;
+            count < 2
+        }));
+        assert_eq!(count, 2);
 
-        c.children.insert(
-            'd',
-            TrieNode {
-                contents: Some('d'),
-                children: FxHashMap::default(),
-                is_terminal: false,
-            },
+        assert_eq!(trie.count_matches("B SS".chars(), 0), 3);
+        assert_eq!(trie.count_matches("B SS".chars(), 30), 2);
+        assert_eq!(trie.count_matches("  ".chars(), 0), 0);
+        assert_eq!(
+            trie.first_matches("    ".chars(), 0, 2),
+            vec!["BASS", "BATS"]
+        );
+        assert_eq!(
+            trie.find_match("    ".chars(), 0, |word, score| word.ends_with('S')
+                && score < 30),
+            Some(String::from("BESS"))
+        );
+        assert_eq!(
+            trie.find_match("    ".chars(), 0, |_, score| score > 60),
+            None
         );
+    }
 
-        root.children.insert('c', c);
+    #[test]
+    fn letter_options_work() {
+        let trie = Trie::build_scored(vec![
+            (String::from("BASS"), 60),
+            (String::from("BATS"), 40),
+            (String::from("BESS"), 20),
+            (String::from("BOSS"), 50),
+        ]);
+
+        let options = trie.letter_options("B SS".chars(), 30);
+        assert_eq!(options.len(), 4);
+        assert_eq!(options.letters(1).collect::<String>(), "AO");
+        assert!(options.contains(0, 'B'));
+        assert!(!options.contains(1, 'E'));
+        assert_eq!(options.count(1, 'A'), None);
+        assert!(!options.is_dead());
+
+        let counts = trie.letter_counts("B   ".chars(), 0);
+        assert_eq!(counts.letters(2).collect::<String>(), "ST");
+        assert_eq!(counts.count(2, 'S'), Some(3));
+        assert_eq!(counts.count(3, 'S'), Some(4));
+        assert_eq!(counts.count(1, 'U'), Some(0));
+
+        let b = 1 << 1;
+        let s = 1 << 18;
+        let options = trie.masked_letter_options(&[b, u64::MAX, s | (1 << 19), s], 45);
+        assert_eq!(options.letters(1).collect::<String>(), "AO");
+        assert_eq!(options.letters(2).collect::<String>(), "S");
+        let options = trie.masked_letter_options(&[b, !(1 << 14), u64::MAX, s], 0);
+        assert_eq!(options.letters(1).collect::<String>(), "AE");
+        assert_eq!(options.letters(2).collect::<String>(), "ST");
+        assert!(trie
+            .masked_letter_options(&[b, 1 << 14, 1 << 19, s], 0)
+            .is_dead());
+        assert_eq!(
+            trie.masked_letter_options(&[b, u64::MAX, u64::MAX, s], 0),
+            WordIndex::masked_letter_options(
+                &BitsetIndex::build_scored(vec![
+                    (String::from("BASS"), 60),
+                    (String::from("BATS"), 40),
+                    (String::from("BESS"), 20),
+                    (String::from("BOSS"), 50),
+                ]),
+                &[b, u64::MAX, u64::MAX, s],
+                0
+            )
+        );
 
-        println!("{}", root);
+        assert!(trie.letter_options("B XS".chars(), 0).is_dead());
+        assert!(trie.letter_options("BASS".chars(), 70).is_dead());
     }
 
     #[test]
-    fn add_sequence_works() {
-        let root = TrieNode {
-            contents: Some('a'),
-            children: FxHashMap::default(),
-            is_terminal: false,
-        };
+    fn tags_work() {
+        let proper = TagSet::default().with(Tag::ProperNoun);
+        let mut trie =
+            Trie::from_reader("Oreo;50;proper\nAREA;50\n".as_bytes(), WordListFormat::Dict)
+                .unwrap();
+
+        assert_eq!(trie.tags("OREO"), proper);
+        assert!(trie.tags("AREA").is_empty());
+        assert!(trie.tags("ALOE").is_empty());
 
-        let new_root = root.add_sequence("itsyaboi");
+        let bytes = bincode::serialize(&trie).unwrap();
+        let copy: Trie = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(copy.tags("OREO"), proper);
 
-        println!("{}", new_root);
+        trie.insert("OREO", 40);
+        assert!(trie.tags("OREO").is_empty());
+        trie.insert_tagged("AREA", 50, proper);
+        trie.remove("AREA");
+        assert!(trie.tags("AREA").is_empty());
+    }
 
-        let another_root = new_root.add_sequence("wereallyouthere");
+    #[test]
+    fn serialization_works() {
+        let mut trie = Trie::build(vec![String::from("BAT"), String::from("ASSET")]);
+        trie.insert("BASS", 70);
 
-        println!("{}", another_root)
+        let bytes = bincode::serialize(&trie).unwrap();
+        let trie: Trie = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(trie.words("   ".chars()), vec!["BAT"]);
+        assert_eq!(trie.words("     ".chars()), vec!["ASSET"]);
+        assert_eq!(trie.score("BASS"), Some(70));
     }
 
     #[test]
@@ -288,13 +1153,63 @@ mod tests {
             String::from("be"),
         ]);
 
-        let expected: HashSet<String> = vec![String::from("bass"), String::from("bess")]
+        let expected: HashSet<String> = [String::from("BASS"), String::from("BESS")]
             .iter()
             .cloned()
             .collect();
 
-        let iter = String::from("b ss");
+        let iter = String::from("B SS");
         let actual: HashSet<String> = trie.words(iter.chars()).iter().cloned().collect();
         assert_eq!(expected, actual,)
     }
+
+    #[test]
+    fn from_reader_works() {
+        let trie =
+            Trie::from_reader("bass;60\nbess;20\n".as_bytes(), WordListFormat::Dict).unwrap();
+        assert_eq!(trie.score("BASS"), Some(60));
+        assert_eq!(trie.score("BESS"), Some(20));
+
+        let err = Trie::from_reader("bass;60\nbess;lots\n".as_bytes(), WordListFormat::Dict)
+            .err()
+            .unwrap();
+        assert!(err.starts_with("Line 2"));
+    }
+
+    #[test]
+    fn scores_work() {
+        let trie = Trie::build_scored(vec![
+            (String::from("bass"), 60),
+            (String::from("bess"), 20),
+            (String::from("be"), 50),
+        ]);
+
+        assert_eq!(trie.score("BASS"), Some(60));
+        assert_eq!(trie.score("BES"), None);
+        assert_eq!(trie.score("BE"), Some(50));
+
+        assert_eq!(
+            trie.words_with_min_score("B SS".chars(), 30),
+            vec![String::from("BASS")]
+        );
+        assert!(trie.is_viable_with_min_score("B SS".chars(), 60));
+        assert!(!trie.is_viable_with_min_score("BE S".chars(), 30));
+        assert_eq!(trie.words("B SS".chars()).len(), 2);
+    }
+
+    #[test]
+    fn normalization_works() {
+        let trie = Trie::build(vec![
+            String::from("Billie Jean King"),
+            String::from("café"),
+            String::from("oreo"),
+            String::from("!!"),
+        ]);
+
+        assert_eq!(trie.score("BILLIEJEANKING"), Some(DEFAULT_SCORE));
+        assert_eq!(trie.display("BILLIEJEANKING"), "Billie Jean King");
+        assert_eq!(trie.display("CAFE"), "café");
+        assert_eq!(trie.display("OREO"), "OREO");
+        assert_eq!(trie.words("    ".chars()).len(), 2);
+    }
 }
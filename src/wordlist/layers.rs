@@ -0,0 +1,305 @@
+/*!
+Composition of several word lists into one, e.g. a base list, a house list of additions
+and score overrides, and a blocklist.
+*/
+
+use std::{collections::BTreeMap, path::Path};
+
+use crate::trie::{Score, Trie};
+
+use super::{load_tagged_words, normalize::Normalizer, tags::TagSet};
+
+enum Layer {
+    Words {
+        name: String,
+        entries: Vec<(String, Score, TagSet)>,
+    },
+    Removals {
+        name: String,
+        words: Vec<String>,
+    },
+    Blocklist {
+        name: String,
+        words: Vec<String>,
+    },
+}
+
+impl Layer {
+    fn name(&self) -> &str {
+        match self {
+            Layer::Words { name, .. } => name,
+            Layer::Removals { name, .. } => name,
+            Layer::Blocklist { name, .. } => name,
+        }
+    }
+}
+
+/// A stack of word lists. Layers are applied in the order they are added: a word
+/// layer adds its words and overrides the scores of words already present, and a
+/// removal layer removes words added by earlier layers (later layers may add them
+/// back). Blocklists are applied after every other layer, so a blocked word never
+/// makes it into the result no matter which layer provides it.
+///
+/// Words are normalized before layers are compared, so `"Oreo"` in one layer
+/// overrides `"OREO"` in another.
+///
+/// ```
+/// use xwords::wordlist::layers::LayeredWordList;
+///
+/// let composition = LayeredWordList::new()
+///     .with_words("base", vec![(String::from("AREA"), 50), (String::from("OREO"), 50)])
+///     .with_words("house", vec![(String::from("OREO"), 20)])
+///     .with_blocklist("banned", vec![String::from("AREA")])
+///     .compose();
+///
+/// assert_eq!(composition.score("OREO"), Some(20));
+/// assert_eq!(composition.source("OREO"), Some("house"));
+/// assert_eq!(composition.blocked_by("AREA"), Some("banned"));
+/// ```
+#[derive(Default)]
+pub struct LayeredWordList {
+    layers: Vec<Layer>,
+}
+
+impl LayeredWordList {
+    pub fn new() -> LayeredWordList {
+        LayeredWordList::default()
+    }
+
+    /// Adds a layer of scored words.
+    pub fn with_words(self, name: &str, entries: Vec<(String, Score)>) -> LayeredWordList {
+        self.with_tagged_words(
+            name,
+            entries
+                .into_iter()
+                .map(|(word, score)| (word, score, TagSet::default()))
+                .collect(),
+        )
+    }
+
+    /// Adds a layer of scored and tagged words. Like scores, a word's tags are
+    /// replaced by those of later layers that include it.
+    pub fn with_tagged_words(
+        mut self,
+        name: &str,
+        entries: Vec<(String, Score, TagSet)>,
+    ) -> LayeredWordList {
+        self.layers.push(Layer::Words {
+            name: String::from(name),
+            entries,
+        });
+        self
+    }
+
+    /// Adds a layer of scored words read from disk, named after the file.
+    pub fn with_file(self, path: &Path) -> Result<LayeredWordList, String> {
+        let entries = load_tagged_words(path)?;
+        Ok(self.with_tagged_words(&path.display().to_string(), entries))
+    }
+
+    /// Adds a layer that removes words added by earlier layers.
+    pub fn with_removals(mut self, name: &str, words: Vec<String>) -> LayeredWordList {
+        self.layers.push(Layer::Removals {
+            name: String::from(name),
+            words,
+        });
+        self
+    }
+
+    /// Adds a blocklist. Blocked words are removed from the result regardless of
+    /// which layer they come from.
+    pub fn with_blocklist(mut self, name: &str, words: Vec<String>) -> LayeredWordList {
+        self.layers.push(Layer::Blocklist {
+            name: String::from(name),
+            words,
+        });
+        self
+    }
+
+    /// Applies every layer, recording where each surviving word came from.
+    pub fn compose(&self) -> Composition {
+        let normalizer = Normalizer::default();
+        let mut entries = BTreeMap::new();
+        for (index, layer) in self.layers.iter().enumerate() {
+            match layer {
+                Layer::Words {
+                    entries: layer_entries,
+                    ..
+                } => {
+                    for (word, score, tags) in layer_entries {
+                        let normalized = normalizer.normalize(word);
+                        if normalized.is_empty() {
+                            continue;
+                        }
+                        let display = normalizer.display_form(word, &normalized);
+                        entries.insert(
+                            normalized,
+                            WordSource {
+                                score: *score,
+                                layer: index,
+                                display,
+                                tags: *tags,
+                            },
+                        );
+                    }
+                }
+                Layer::Removals { words, .. } => {
+                    for word in words {
+                        entries.remove(&normalizer.normalize(word));
+                    }
+                }
+                Layer::Blocklist { .. } => {}
+            }
+        }
+
+        let mut blocked = BTreeMap::new();
+        for (index, layer) in self.layers.iter().enumerate() {
+            if let Layer::Blocklist { words, .. } = layer {
+                for word in words {
+                    let normalized = normalizer.normalize(word);
+                    if entries.remove(&normalized).is_some() {
+                        blocked.insert(normalized, index);
+                    }
+                }
+            }
+        }
+
+        Composition {
+            layer_names: self
+                .layers
+                .iter()
+                .map(|layer| String::from(layer.name()))
+                .collect(),
+            entries,
+            blocked,
+        }
+    }
+}
+
+/// The score of a composed word, the index of the layer that provided it, the form
+/// it was written in if that differs from its normalization by more than case, and
+/// its tags.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WordSource {
+    pub score: Score,
+    pub layer: usize,
+    pub display: Option<String>,
+    pub tags: TagSet,
+}
+
+/// The result of composing a `LayeredWordList`.
+pub struct Composition {
+    layer_names: Vec<String>,
+    /// Every normalized word in the composed list, in sorted order.
+    pub entries: BTreeMap<String, WordSource>,
+    /// Words that were present before blocklists were applied, and the index of the
+    /// blocklist that removed them.
+    pub blocked: BTreeMap<String, usize>,
+}
+
+impl Composition {
+    pub fn layer_name(&self, layer: usize) -> &str {
+        &self.layer_names[layer]
+    }
+
+    pub fn score(&self, word: &str) -> Option<Score> {
+        self.entries.get(word).map(|source| source.score)
+    }
+
+    /// The name of the layer that provided `word`'s final score.
+    pub fn source(&self, word: &str) -> Option<&str> {
+        self.entries
+            .get(word)
+            .map(|source| self.layer_name(source.layer))
+    }
+
+    /// The name of the blocklist that removed `word`, if one did.
+    pub fn blocked_by(&self, word: &str) -> Option<&str> {
+        self.blocked.get(word).map(|layer| self.layer_name(*layer))
+    }
+
+    /// Lists every word with its score and the name of the layer it came from.
+    pub fn audit(&self) -> impl Iterator<Item = (&str, Score, &str)> {
+        self.entries
+            .iter()
+            .map(move |(word, source)| (word.as_str(), source.score, self.layer_name(source.layer)))
+    }
+
+    pub fn to_trie(&self) -> Trie {
+        Trie::build_tagged(
+            self.entries
+                .iter()
+                .map(|(word, source)| {
+                    (
+                        source.display.clone().unwrap_or_else(|| word.clone()),
+                        source.score,
+                        source.tags,
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LayeredWordList;
+
+    fn entries(words: &[(&str, u32)]) -> Vec<(String, u32)> {
+        words
+            .iter()
+            .map(|(word, score)| (String::from(*word), *score))
+            .collect()
+    }
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| String::from(*word)).collect()
+    }
+
+    #[test]
+    fn compose_works() {
+        let composition = LayeredWordList::new()
+            .with_blocklist("offensive", words(&["DDDD"]))
+            .with_words(
+                "base",
+                entries(&[("AAAA", 50), ("BBBB", 50), ("CCCC", 50), ("EEEE", 50)]),
+            )
+            .with_words("house", entries(&[("BBBB", 70), ("DDDD", 60)]))
+            .with_removals("cuts", words(&["CCCC", "EEEE"]))
+            .with_words("late", entries(&[("EEEE", 10)]))
+            .with_blocklist("banned", words(&["AAAA", "ZZZZ"]))
+            .compose();
+
+        assert_eq!(
+            composition.audit().collect::<Vec<_>>(),
+            vec![("BBBB", 70, "house"), ("EEEE", 10, "late")]
+        );
+        assert_eq!(composition.blocked_by("AAAA"), Some("banned"));
+        assert_eq!(composition.blocked_by("DDDD"), Some("offensive"));
+        assert_eq!(composition.blocked_by("ZZZZ"), None);
+        assert_eq!(composition.source("CCCC"), None);
+
+        let trie = composition.to_trie();
+        assert_eq!(trie.score("BBBB"), Some(70));
+        assert_eq!(trie.score("DDDD"), None);
+    }
+
+    #[test]
+    fn compose_normalizes_words() {
+        let composition = LayeredWordList::new()
+            .with_words("base", entries(&[("oreo", 50), ("New York", 50)]))
+            .with_words("house", entries(&[("Oreo", 20)]))
+            .with_blocklist("banned", words(&["new york"]))
+            .compose();
+
+        assert_eq!(composition.score("OREO"), Some(20));
+        assert_eq!(composition.source("OREO"), Some("house"));
+        assert_eq!(composition.blocked_by("NEWYORK"), Some("banned"));
+
+        let composition = LayeredWordList::new()
+            .with_words("base", entries(&[("Billie Jean King", 50)]))
+            .compose();
+        let trie = composition.to_trie();
+        assert_eq!(trie.display("BILLIEJEANKING"), "Billie Jean King");
+    }
+}
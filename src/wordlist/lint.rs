@@ -0,0 +1,263 @@
+/*!
+Checks word lists for entries that are unlikely to be intended, and collects
+statistics about them.
+
+Lists that are edited by hand or merged from several sources tend to accumulate
+duplicates, entries with digits or stray punctuation, and words that are too short or
+too long to ever appear in a grid. `Linter` reports these, along with the length,
+score and letter distributions of the list, and its `LintReport` serializes to JSON.
+*/
+
+use std::{collections::BTreeMap, io::BufRead, path::Path};
+
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::trie::Score;
+
+use super::{normalize::Normalizer, read_words, WordListFormat};
+
+/// Characters that separate the words of a phrase, such as `rock 'n' roll` or
+/// `x-ray`. They are dropped by normalization, but are not worth reporting.
+const SEPARATORS: [char; 3] = [' ', '-', '\''];
+
+/// A problem with one entry of a word list.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct LintIssue {
+    /// The position of the entry in the list, starting from 1. Blank lines are not
+    /// counted.
+    pub entry: usize,
+    /// The entry as it was written in the list.
+    pub word: String,
+    #[serde(flatten)]
+    pub kind: LintKind,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LintKind {
+    /// The entry contains characters, such as digits or punctuation, that are
+    /// neither letters nor separators and are dropped by normalization.
+    InvalidCharacters { characters: Vec<char> },
+    /// The entry normalizes to the same word as an earlier entry.
+    Duplicate {
+        normalized: String,
+        first_entry: usize,
+    },
+    /// The normalized entry is shorter than the minimum length.
+    TooShort { length: usize },
+    /// The normalized entry is longer than the maximum length.
+    TooLong { length: usize },
+}
+
+/// The result of linting a word list.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize)]
+pub struct LintReport {
+    /// The number of entries read.
+    pub entries: usize,
+    /// The number of distinct normalized words.
+    pub words: usize,
+    pub issues: Vec<LintIssue>,
+    /// The number of distinct normalized words of each length.
+    pub lengths: BTreeMap<usize, usize>,
+    /// The number of entries with each score.
+    pub scores: BTreeMap<Score, usize>,
+    /// How often each letter appears across the distinct normalized words.
+    pub letters: BTreeMap<char, usize>,
+}
+
+impl LintReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("lint reports always serialize")
+    }
+}
+
+/// Lints word lists. By default it flags words shorter than 3 letters, which
+/// crossword grids do not allow, and longer than 21 letters, the width of the
+/// largest common grid.
+#[derive(Debug, Clone)]
+pub struct Linter {
+    normalizer: Normalizer,
+    min_length: usize,
+    max_length: usize,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Linter::new()
+    }
+}
+
+impl Linter {
+    pub fn new() -> Linter {
+        Linter {
+            normalizer: Normalizer::default(),
+            min_length: 3,
+            max_length: 21,
+        }
+    }
+
+    /// Lints against a different alphabet, e.g. one that allows digits.
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Linter {
+        self.normalizer = normalizer;
+        self
+    }
+
+    pub fn with_min_length(mut self, min_length: usize) -> Linter {
+        self.min_length = min_length;
+        self
+    }
+
+    pub fn with_max_length(mut self, max_length: usize) -> Linter {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Lints a word list in any `WordListFormat`. Errors are only returned for lists
+    /// that cannot be parsed at all.
+    pub fn lint<R: BufRead>(
+        &self,
+        reader: R,
+        format: WordListFormat,
+    ) -> Result<LintReport, String> {
+        let mut report = LintReport::default();
+        let mut first_entries: FxHashMap<String, usize> = FxHashMap::default();
+        read_words(reader, format, |word, score| {
+            report.entries += 1;
+            let entry = report.entries;
+            *report.scores.entry(score).or_default() += 1;
+            let mut issue = |kind| {
+                report.issues.push(LintIssue {
+                    entry,
+                    word: word.clone(),
+                    kind,
+                })
+            };
+
+            let characters = self.invalid_characters(&word);
+            if !characters.is_empty() {
+                issue(LintKind::InvalidCharacters { characters });
+            }
+
+            let normalized = self.normalizer.normalize(&word);
+            let length = normalized.chars().count();
+            if length < self.min_length {
+                issue(LintKind::TooShort { length });
+            } else if length > self.max_length {
+                issue(LintKind::TooLong { length });
+            }
+
+            if let Some(first_entry) = first_entries.get(&normalized) {
+                issue(LintKind::Duplicate {
+                    normalized,
+                    first_entry: *first_entry,
+                });
+                return;
+            }
+
+            *report.lengths.entry(length).or_default() += 1;
+            for c in normalized.chars() {
+                *report.letters.entry(c).or_default() += 1;
+            }
+            first_entries.insert(normalized, entry);
+        })?;
+        report.words = first_entries.len();
+        Ok(report)
+    }
+
+    /// Lints a word list on disk, choosing the format by extension and falling back
+    /// to `WordListFormat::Dict`.
+    pub fn lint_file(&self, path: &Path) -> Result<LintReport, String> {
+        let file = std::fs::File::open(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let format = WordListFormat::from_path(path).unwrap_or(WordListFormat::Dict);
+        self.lint(std::io::BufReader::new(file), format)
+            .map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    /// Returns the distinct characters of `word` that normalize to nothing, other
+    /// than separators.
+    fn invalid_characters(&self, word: &str) -> Vec<char> {
+        let mut result = vec![];
+        let mut buffer = [0; 4];
+        for c in word.chars() {
+            if !SEPARATORS.contains(&c)
+                && !result.contains(&c)
+                && self
+                    .normalizer
+                    .normalize(c.encode_utf8(&mut buffer))
+                    .is_empty()
+            {
+                result.push(c);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wordlist::WordListFormat;
+
+    use super::{LintKind, Linter};
+
+    #[test]
+    fn lint_works() {
+        let report = Linter::new()
+            .with_max_length(9)
+            .lint(
+                "AREA;50\nR2D2;40\nrock 'n' roll;30\nArea;20\nOX;50\nOREO!;50\nABCDEFGHIJ;50\n"
+                    .as_bytes(),
+                WordListFormat::Dict,
+            )
+            .unwrap();
+
+        assert_eq!(report.entries, 7);
+        assert_eq!(report.words, 6);
+        let issues: Vec<_> = report
+            .issues
+            .iter()
+            .map(|issue| (issue.entry, issue.kind.clone()))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                (
+                    2,
+                    LintKind::InvalidCharacters {
+                        characters: vec!['2']
+                    }
+                ),
+                (2, LintKind::TooShort { length: 2 }),
+                (
+                    4,
+                    LintKind::Duplicate {
+                        normalized: String::from("AREA"),
+                        first_entry: 1
+                    }
+                ),
+                (5, LintKind::TooShort { length: 2 }),
+                (
+                    6,
+                    LintKind::InvalidCharacters {
+                        characters: vec!['!']
+                    }
+                ),
+                (7, LintKind::TooLong { length: 10 }),
+            ]
+        );
+        assert_eq!(report.lengths.get(&2), Some(&2));
+        assert_eq!(report.lengths.get(&4), Some(&2));
+        assert_eq!(report.scores.get(&50), Some(&4));
+        assert_eq!(report.letters.get(&'R'), Some(&5));
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["issues"][2]["kind"], "duplicate");
+        assert_eq!(json["issues"][2]["first_entry"], 1);
+        assert_eq!(json["lengths"]["4"], 2);
+    }
+}
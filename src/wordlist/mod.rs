@@ -0,0 +1,376 @@
+/*!
+Loaders for word lists stored as text.
+*/
+
+use std::{fmt, io::BufRead, path::Path};
+
+use serde::{
+    de::{self, DeserializeSeed, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+use crate::trie::{Score, DEFAULT_SCORE};
+
+use self::tags::TagSet;
+
+pub mod layers;
+pub mod lint;
+pub mod normalize;
+pub mod tags;
+
+/// The on-disk formats a word list can be read from.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum WordListFormat {
+    /// One word per line.
+    Text,
+    /// One `WORD;score` entry per line, optionally followed by comma separated tags:
+    /// `WORD;score;proper,abbr`.
+    Dict,
+    /// A JSON array of words, like `wordlist.json`. Elements may also be
+    /// `[word, score]` pairs or `[word, score, [tags]]` triples.
+    Json,
+}
+
+impl WordListFormat {
+    /// Guesses a format from a file extension: `.txt`, `.dict` or `.json`.
+    pub fn from_path(path: &Path) -> Option<WordListFormat> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "txt" => Some(WordListFormat::Text),
+            "dict" => Some(WordListFormat::Dict),
+            "json" => Some(WordListFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Streams the entries of a word list to `visit` as they are parsed, so that the whole
+/// list never needs to be held in memory. Returns the number of entries read.
+///
+/// Errors name the line they occurred on.
+pub fn read_words<R: BufRead, F: FnMut(String, Score)>(
+    reader: R,
+    format: WordListFormat,
+    mut visit: F,
+) -> Result<usize, String> {
+    read_tagged_words(reader, format, |word, score, _| visit(word, score))
+}
+
+/// Like `read_words`, but also passes each entry's tags to `visit`. Entries without
+/// tags get an empty `TagSet`.
+pub fn read_tagged_words<R: BufRead, F: FnMut(String, Score, TagSet)>(
+    mut reader: R,
+    format: WordListFormat,
+    mut visit: F,
+) -> Result<usize, String> {
+    if format == WordListFormat::Json {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let count = JsonWords { visit: &mut visit }
+            .deserialize(&mut deserializer)
+            .and_then(|count| deserializer.end().map(|_| count))
+            .map_err(|err| {
+                // serde_json appends the position to its messages; report it like the
+                // line based formats do instead.
+                let message = err.to_string();
+                let message = message
+                    .rsplit_once(" at line ")
+                    .map_or(message.as_str(), |(message, _)| message);
+                format!("Line {}: {}", err.line(), message)
+            })?;
+        return Ok(count);
+    }
+
+    let mut count = 0;
+    let mut line = String::new();
+    let mut line_number = 0;
+    loop {
+        line.clear();
+        line_number += 1;
+        let read = reader
+            .read_line(&mut line)
+            .map_err(|err| format!("Line {}: {}", line_number, err))?;
+        if read == 0 {
+            return Ok(count);
+        }
+
+        let entry = match format {
+            WordListFormat::Dict => {
+                parse_tagged_line(&line).map_err(|err| format!("Line {}: {}", line_number, err))?
+            }
+            _ => {
+                let word = line.trim();
+                if word.is_empty() {
+                    None
+                } else {
+                    Some((String::from(word), DEFAULT_SCORE, TagSet::default()))
+                }
+            }
+        };
+
+        if let Some((word, score, tags)) = entry {
+            visit(word, score, tags);
+            count += 1;
+        }
+    }
+}
+
+struct JsonEntry(String, Score, TagSet);
+
+impl<'de> Deserialize<'de> for JsonEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<JsonEntry, D::Error> {
+        deserializer.deserialize_any(JsonEntryVisitor)
+    }
+}
+
+struct JsonEntryVisitor;
+
+impl<'de> Visitor<'de> for JsonEntryVisitor {
+    type Value = JsonEntry;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a word, a [word, score] pair or a [word, score, [tags]] triple"
+        )
+    }
+
+    fn visit_str<E: de::Error>(self, word: &str) -> Result<JsonEntry, E> {
+        Ok(JsonEntry(
+            String::from(word),
+            DEFAULT_SCORE,
+            TagSet::default(),
+        ))
+    }
+
+    fn visit_string<E: de::Error>(self, word: String) -> Result<JsonEntry, E> {
+        Ok(JsonEntry(word, DEFAULT_SCORE, TagSet::default()))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonEntry, A::Error> {
+        let word = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let score = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let mut tags = TagSet::default();
+        if let Some(names) = seq.next_element::<Vec<String>>()? {
+            for name in names {
+                tags.insert(name.parse().map_err(de::Error::custom)?);
+            }
+        }
+        if seq.next_element::<de::IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(4, &self));
+        }
+        Ok(JsonEntry(word, score, tags))
+    }
+}
+
+struct JsonWords<'s, F> {
+    visit: &'s mut F,
+}
+
+impl<'de, 's, F: FnMut(String, Score, TagSet)> DeserializeSeed<'de> for JsonWords<'s, F> {
+    type Value = usize;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 's, F: FnMut(String, Score, TagSet)> Visitor<'de> for JsonWords<'s, F> {
+    type Value = usize;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an array of words or [word, score] pairs")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<usize, A::Error> {
+        let mut count = 0;
+        while let Some(JsonEntry(word, score, tags)) = seq.next_element()? {
+            (self.visit)(word, score, tags);
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+/// Parses one line of a scored word list in the `WORD;50` style used by the Peter Broda
+/// and Spread the Wordlist lists. Lines without a score are given `DEFAULT_SCORE`.
+/// Returns `Ok(None)` for blank lines.
+pub fn parse_scored_line(line: &str) -> Result<Option<(String, Score)>, String> {
+    Ok(parse_tagged_line(line)?.map(|(word, score, _)| (word, score)))
+}
+
+/// Like `parse_scored_line`, but also accepts tags after a second `;`, as in
+/// `WORD;50;proper,abbr`.
+pub fn parse_tagged_line(line: &str) -> Result<Option<(String, Score, TagSet)>, String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+
+    let (rest, last) = match line.rsplit_once(';') {
+        Some(split) => split,
+        None => return Ok(Some((String::from(line), DEFAULT_SCORE, TagSet::default()))),
+    };
+    let parse_score = |word: &str, score: &str| {
+        score
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid score {:?} for {:?}.", score, word.trim()))
+    };
+
+    // A last field that isn't a score holds the tags of a `WORD;score;tags` entry.
+    let (word, score, tags) = match (last.trim().parse(), rest.rsplit_once(';')) {
+        (Ok(score), _) => (rest, score, TagSet::default()),
+        (Err(_), Some((word, score))) => (word, parse_score(word, score)?, TagSet::parse(last)?),
+        (Err(_), None) => return Err(parse_score(rest, last).unwrap_err()),
+    };
+    Ok(Some((String::from(word.trim()), score, tags)))
+}
+
+/// Parses a scored word list with one `WORD;score` entry per line.
+pub fn parse_scored_words(contents: &str) -> Result<Vec<(String, Score)>, String> {
+    let mut result = vec![];
+    read_words(contents.as_bytes(), WordListFormat::Dict, |word, score| {
+        result.push((word, score))
+    })?;
+    Ok(result)
+}
+
+/// Reads a word list from disk into memory, choosing the format by extension and
+/// falling back to `WordListFormat::Dict`.
+pub fn load_scored_words(path: &Path) -> Result<Vec<(String, Score)>, String> {
+    Ok(load_tagged_words(path)?
+        .into_iter()
+        .map(|(word, score, _)| (word, score))
+        .collect())
+}
+
+/// Like `load_scored_words`, but keeps each entry's tags.
+pub fn load_tagged_words(path: &Path) -> Result<Vec<(String, Score, TagSet)>, String> {
+    let file = std::fs::File::open(path)
+        .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+    let format = WordListFormat::from_path(path).unwrap_or(WordListFormat::Dict);
+    let mut result = vec![];
+    read_tagged_words(
+        std::io::BufReader::new(file),
+        format,
+        |word, score, tags| result.push((word, score, tags)),
+    )
+    .map_err(|err| format!("{}: {}", path.display(), err))?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::trie::DEFAULT_SCORE;
+
+    use super::{
+        parse_scored_words, read_tagged_words, read_words,
+        tags::{Tag, TagSet},
+        WordListFormat,
+    };
+
+    fn read_all(contents: &str, format: WordListFormat) -> Result<Vec<(String, u32)>, String> {
+        let mut result = vec![];
+        read_words(contents.as_bytes(), format, |word, score| {
+            result.push((word, score))
+        })?;
+        Ok(result)
+    }
+
+    #[test]
+    fn parse_scored_words_works() {
+        assert_eq!(
+            parse_scored_words("AREA;50\n\nzyzzyva;25\nOREO\n").unwrap(),
+            vec![
+                (String::from("AREA"), 50),
+                (String::from("zyzzyva"), 25),
+                (String::from("OREO"), DEFAULT_SCORE),
+            ]
+        );
+
+        let err = parse_scored_words("AREA;50\nOREO;many").unwrap_err();
+        assert!(err.starts_with("Line 2"));
+    }
+
+    #[test]
+    fn read_words_works() {
+        let expected = vec![
+            (String::from("AREA"), DEFAULT_SCORE),
+            (String::from("OREO"), DEFAULT_SCORE),
+        ];
+        assert_eq!(
+            read_all("AREA\r\n\nOREO", WordListFormat::Text).unwrap(),
+            expected
+        );
+        assert_eq!(
+            read_all("[\"AREA\",\n \"OREO\"]", WordListFormat::Json).unwrap(),
+            expected
+        );
+        assert_eq!(
+            read_all("[[\"AREA\", 10], \"OREO\"]", WordListFormat::Json).unwrap(),
+            vec![
+                (String::from("AREA"), 10),
+                (String::from("OREO"), DEFAULT_SCORE),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_words_reports_lines() {
+        let err = read_all("[\n\"AREA\",\n3, \"OREO\"\n]", WordListFormat::Json).unwrap_err();
+        assert!(err.starts_with("Line 3"), "{}", err);
+
+        let err = read_all("[\"AREA\"] trailing", WordListFormat::Json).unwrap_err();
+        assert!(err.starts_with("Line 1"), "{}", err);
+    }
+
+    #[test]
+    fn read_tagged_words_works() {
+        let read_tagged = |contents: &str, format| {
+            let mut result = vec![];
+            read_tagged_words(contents.as_bytes(), format, |word, score, tags| {
+                result.push((word, score, tags))
+            })
+            .map(|_| result)
+        };
+        let proper = TagSet::default().with(Tag::ProperNoun);
+        let expected = vec![
+            (String::from("OREO"), 50, proper),
+            (
+                String::from("ATA"),
+                20,
+                TagSet::default().with(Tag::Partial),
+            ),
+            (String::from("AREA"), 50, TagSet::default()),
+        ];
+
+        assert_eq!(
+            read_tagged(
+                "OREO;50;proper\nATA;20;partial\nAREA;50\n",
+                WordListFormat::Dict
+            ),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            read_tagged(
+                "[[\"OREO\", 50, [\"proper\"]], [\"ATA\", 20, [\"partial\"]], [\"AREA\", 50]]",
+                WordListFormat::Json
+            ),
+            Ok(expected)
+        );
+        assert_eq!(
+            read_tagged("OREO;50;proper,\n", WordListFormat::Dict),
+            Ok(vec![(String::from("OREO"), 50, proper)])
+        );
+
+        let err = read_tagged("AREA;50\nOREO;50;slang", WordListFormat::Dict).unwrap_err();
+        assert!(err.starts_with("Line 2"), "{}", err);
+        let err = read_tagged("OREO;many;proper", WordListFormat::Dict).unwrap_err();
+        assert!(err.contains("Invalid score"), "{}", err);
+        assert!(read_tagged("[[\"OREO\", 50, [\"slang\"]]]", WordListFormat::Json).is_err());
+    }
+}
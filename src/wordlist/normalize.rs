@@ -0,0 +1,201 @@
+/*!
+Normalization of word list entries and grid letters to a common alphabet.
+
+Word lists come in many shapes: `wordlist.json` is uppercase, other lists are lowercase,
+and some include phrases such as "rock 'n' roll". Before a word can be placed in a grid
+it is folded to uppercase and stripped of everything outside the alphabet, while the
+original phrase is kept around as a display form.
+*/
+
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+const MAX_LETTERS: usize = 64;
+
+/// The letters that may appear in a normalized word.
+///
+/// Grids store one byte per square, so alphabets are limited to ASCII characters, and
+/// tries index children with a 64 bit mask, so an alphabet has at most 64 letters.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Alphabet {
+    letters: Vec<char>,
+}
+
+impl Alphabet {
+    /// The letters `A` through `Z`.
+    pub fn english() -> Alphabet {
+        Alphabet {
+            letters: ('A'..='Z').collect(),
+        }
+    }
+
+    /// Creates an alphabet from the characters of `letters`, e.g. `"ABC...Z0123456789"`
+    /// for lists that allow digits. Letters should be uppercase, since words are
+    /// uppercased before they are checked against the alphabet.
+    pub fn new(letters: &str) -> Result<Alphabet, String> {
+        let mut result: Vec<char> = letters.chars().collect();
+        if let Some(c) = result
+            .iter()
+            .find(|c| !c.is_ascii() || c.is_ascii_whitespace() || **c == '*')
+        {
+            return Err(format!("{:?} cannot be part of an alphabet.", c));
+        }
+        result.sort_unstable();
+        result.dedup();
+        if result.len() > MAX_LETTERS {
+            return Err(format!(
+                "An alphabet can have at most {} letters.",
+                MAX_LETTERS
+            ));
+        }
+        Ok(Alphabet { letters: result })
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.letters.binary_search(&c).is_ok()
+    }
+
+    /// The position of `c` in `letters`, if it is part of this alphabet.
+    pub fn index_of(&self, c: char) -> Option<usize> {
+        self.letters.binary_search(&c).ok()
+    }
+
+    /// The letters of this alphabet, in sorted order.
+    pub fn letters(&self) -> &[char] {
+        &self.letters
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Alphabet {
+        Alphabet::english()
+    }
+}
+
+/// Letters that do not decompose into a base letter and diacritics, but have a
+/// conventional spelling in the English alphabet.
+const LIGATURES: &[(char, &str)] = &[
+    ('Æ', "AE"),
+    ('Œ', "OE"),
+    ('Ø', "O"),
+    ('Ł', "L"),
+    ('Đ', "D"),
+    ('Þ', "TH"),
+];
+
+/// Folds words and grid letters into an `Alphabet`.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub struct Normalizer {
+    alphabet: Alphabet,
+}
+
+impl Normalizer {
+    pub fn new(alphabet: Alphabet) -> Normalizer {
+        Normalizer { alphabet }
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// Normalizes a word: letters are uppercased, diacritics are stripped unless the
+    /// accented letter is itself part of the alphabet, and anything else outside the
+    /// alphabet (spaces, punctuation, digits) is dropped.
+    ///
+    /// For example, `"Rock 'n' roll"` becomes `"ROCKNROLL"` and `"Café"` becomes
+    /// `"CAFE"`.
+    pub fn normalize(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        for c in text.chars().flat_map(char::to_uppercase) {
+            if self.alphabet.contains(c) {
+                result.push(c);
+            } else if let Some((_, spelling)) =
+                LIGATURES.iter().find(|(ligature, _)| *ligature == c)
+            {
+                result.extend(spelling.chars().filter(|c| self.alphabet.contains(*c)));
+            } else {
+                result.extend(
+                    std::iter::once(c)
+                        .nfd()
+                        .filter(|c| self.alphabet.contains(*c)),
+                );
+            }
+        }
+        result
+    }
+
+    /// Returns `text` as a display form if it carries more information than its
+    /// normalization, i.e. if the two differ by more than case.
+    pub fn display_form(&self, text: &str, normalized: &str) -> Option<String> {
+        if text
+            .chars()
+            .flat_map(char::to_uppercase)
+            .eq(normalized.chars())
+        {
+            None
+        } else {
+            Some(String::from(text))
+        }
+    }
+
+    /// Normalizes a single grid square. Shaded (`*`) and blank (` `) squares, and
+    /// any other ASCII markers, are left alone; everything else must normalize to
+    /// exactly one letter of the alphabet, since grids store one byte per square.
+    pub fn normalize_square(&self, c: char) -> Result<char, String> {
+        if c.is_ascii() && !c.is_ascii_alphabetic() {
+            return Ok(c);
+        }
+        let normalized = self.normalize(&c.to_string());
+        let mut chars = normalized.chars();
+        match (chars.next(), chars.next()) {
+            (Some(result), None) => Ok(result),
+            _ => Err(format!("{:?} cannot be used in a grid square.", c)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Alphabet, Normalizer};
+
+    #[test]
+    fn normalize_works() {
+        let normalizer = Normalizer::default();
+
+        assert_eq!(normalizer.normalize("New York"), "NEWYORK");
+        assert_eq!(normalizer.normalize("rock 'n' roll"), "ROCKNROLL");
+        assert_eq!(normalizer.normalize("Café Olé"), "CAFEOLE");
+        assert_eq!(normalizer.normalize("Ærøskøbing"), "AEROSKOBING");
+        assert_eq!(normalizer.normalize("Straße"), "STRASSE");
+        assert_eq!(normalizer.normalize("R2-D2"), "RD");
+
+        let with_digits =
+            Normalizer::new(Alphabet::new("ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789").unwrap());
+        assert_eq!(with_digits.normalize("R2-D2"), "R2D2");
+    }
+
+    #[test]
+    fn display_form_works() {
+        let normalizer = Normalizer::default();
+
+        assert_eq!(
+            normalizer.display_form("Billie Jean King", "BILLIEJEANKING"),
+            Some(String::from("Billie Jean King"))
+        );
+        assert_eq!(normalizer.display_form("oreo", "OREO"), None);
+    }
+
+    #[test]
+    fn normalize_square_works() {
+        let normalizer = Normalizer::default();
+
+        assert_eq!(normalizer.normalize_square('é'), Ok('E'));
+        assert_eq!(normalizer.normalize_square('*'), Ok('*'));
+        assert_eq!(normalizer.normalize_square(' '), Ok(' '));
+        assert!(normalizer.normalize_square('Æ').is_err());
+        assert!(normalizer.normalize_square('—').is_err());
+        assert!(normalizer.normalize_square('•').is_err());
+        assert!(Alphabet::new("AÑ").is_err());
+        assert!(Alphabet::new(&('+'..='~').collect::<String>()).is_err());
+    }
+}
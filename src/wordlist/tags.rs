@@ -0,0 +1,151 @@
+/*!
+Metadata tags for word list entries, e.g. whether an entry is a proper noun or a
+partial phrase. Fills can limit how many tagged entries they use; see
+`Filler::with_tag_limit`.
+*/
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Tag {
+    ProperNoun,
+    Abbreviation,
+    /// A fragment of a phrase, e.g. `ATA` in "one ___ time".
+    Partial,
+    Foreign,
+    /// Obscure words that mostly appear in crosswords, e.g. `ESNE`.
+    Crosswordese,
+    Vulgar,
+}
+
+impl Tag {
+    pub const ALL: [Tag; 6] = [
+        Tag::ProperNoun,
+        Tag::Abbreviation,
+        Tag::Partial,
+        Tag::Foreign,
+        Tag::Crosswordese,
+        Tag::Vulgar,
+    ];
+
+    /// The name used for this tag in word list files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tag::ProperNoun => "proper",
+            Tag::Abbreviation => "abbr",
+            Tag::Partial => "partial",
+            Tag::Foreign => "foreign",
+            Tag::Crosswordese => "crosswordese",
+            Tag::Vulgar => "vulgar",
+        }
+    }
+
+    fn bit(&self) -> u8 {
+        1 << Tag::ALL.iter().position(|tag| tag == self).unwrap()
+    }
+}
+
+impl FromStr for Tag {
+    type Err = String;
+
+    /// Parses a tag name, case-insensitively. Common spellings such as `abbreviation`
+    /// and `proper-noun` are accepted too.
+    fn from_str(name: &str) -> Result<Tag, String> {
+        match name.trim().to_lowercase().as_str() {
+            "proper" | "proper-noun" | "propernoun" => Ok(Tag::ProperNoun),
+            "abbr" | "abbreviation" => Ok(Tag::Abbreviation),
+            "partial" => Ok(Tag::Partial),
+            "foreign" => Ok(Tag::Foreign),
+            "crosswordese" | "xwordese" => Ok(Tag::Crosswordese),
+            "vulgar" => Ok(Tag::Vulgar),
+            _ => Err(format!("Unknown tag {:?}.", name.trim())),
+        }
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A set of `Tag`s.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash, Serialize, Deserialize)]
+pub struct TagSet(u8);
+
+impl TagSet {
+    /// Parses a comma separated list of tag names such as `proper,abbr`.
+    pub fn parse(names: &str) -> Result<TagSet, String> {
+        let mut result = TagSet::default();
+        for name in names.split(',').filter(|name| !name.trim().is_empty()) {
+            result.insert(name.parse()?);
+        }
+        Ok(result)
+    }
+
+    /// Returns the set as a bitmask, for storing in binary formats.
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Reads a set from a bitmask returned by `bits`, ignoring unknown bits.
+    pub fn from_bits(bits: u8) -> TagSet {
+        TagSet(bits & ((1 << Tag::ALL.len()) - 1))
+    }
+
+    pub fn with(mut self, tag: Tag) -> TagSet {
+        self.insert(tag);
+        self
+    }
+
+    pub fn insert(&mut self, tag: Tag) {
+        self.0 |= tag.bit();
+    }
+
+    pub fn contains(&self, tag: Tag) -> bool {
+        self.0 & tag.bit() != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Tag> + '_ {
+        Tag::ALL
+            .iter()
+            .copied()
+            .filter(move |tag| self.contains(*tag))
+    }
+}
+
+impl fmt::Display for TagSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, tag) in self.iter().enumerate() {
+            if index > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", tag)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Tag, TagSet};
+
+    #[test]
+    fn tag_set_works() {
+        let tags = TagSet::parse("Abbreviation, partial,").unwrap();
+
+        assert!(tags.contains(Tag::Abbreviation));
+        assert!(tags.contains(Tag::Partial));
+        assert!(!tags.contains(Tag::Vulgar));
+        assert_eq!(tags.to_string(), "abbr,partial");
+        assert_eq!(TagSet::parse(&tags.to_string()), Ok(tags));
+        assert!(TagSet::parse("").unwrap().is_empty());
+        assert!(TagSet::parse("slang").is_err());
+    }
+}